@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE subscription_state
+(
+    subscription TEXT     NOT NULL PRIMARY KEY,
+    last_updated DATETIME NOT NULL,
+    created      DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE oai_harvests
+(
+    harvest          TEXT     NOT NULL PRIMARY KEY,
+    resumption_token TEXT,
+    updated          DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE paper_categories
+(
+    paper_id    INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    PRIMARY KEY (paper_id, category_id),
+    FOREIGN KEY (paper_id) REFERENCES papers (id),
+    FOREIGN KEY (category_id) REFERENCES categories (id)
+);
+
+-- 既存の論文はメインカテゴリだけ登録しておく
+INSERT INTO paper_categories (paper_id, category_id)
+SELECT id, category_id
+FROM papers;
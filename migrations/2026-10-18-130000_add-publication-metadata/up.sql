@@ -0,0 +1,22 @@
+-- Your SQL goes here
+ALTER TABLE papers ADD journal_ref TEXT;
+ALTER TABLE papers ADD doi TEXT;
+
+CREATE TABLE paper_affiliations
+(
+    paper_id  INTEGER NOT NULL,
+    author_id INTEGER NOT NULL,
+    name      TEXT    NOT NULL,
+    PRIMARY KEY (paper_id, author_id, name),
+    FOREIGN KEY (paper_id) REFERENCES papers (id),
+    FOREIGN KEY (author_id) REFERENCES authors (id)
+);
+
+-- ACM Computing Classification SystemやMSCの分類
+CREATE TABLE paper_classes
+(
+    paper_id INTEGER NOT NULL,
+    name     TEXT    NOT NULL,
+    PRIMARY KEY (paper_id, name),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
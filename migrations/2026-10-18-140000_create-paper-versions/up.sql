@@ -0,0 +1,28 @@
+-- Your SQL goes here
+-- バージョンを除いたarXiv ID。論文はこれで同一視し、新しいバージョンは行を更新する
+ALTER TABLE papers ADD arxiv_id TEXT;
+
+CREATE TABLE paper_versions
+(
+    paper_id INTEGER  NOT NULL,
+    version  INTEGER  NOT NULL,
+    title    TEXT     NOT NULL,
+    summary  TEXT     NOT NULL,
+    comment  TEXT     NOT NULL,
+    updated  DATETIME NOT NULL,
+    PRIMARY KEY (paper_id, version),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
+
+-- 既存の論文はURL（http://arxiv.org/abs/2104.01234v2 など）からIDとバージョンを取り出す
+UPDATE papers SET arxiv_id = substr(url, instr(url, '/abs/') + 5)
+ WHERE instr(url, '/abs/') > 0;
+INSERT INTO paper_versions(paper_id, version, title, summary, comment, updated)
+ SELECT id, CAST(substr(arxiv_id, length(rtrim(arxiv_id, '0123456789')) + 1) AS INTEGER), title, summary, comment, updated
+ FROM papers
+ WHERE rtrim(arxiv_id, '0123456789') LIKE '%v' AND rtrim(arxiv_id, '0123456789') <> arxiv_id;
+UPDATE papers SET arxiv_id = substr(arxiv_id, 1, length(rtrim(arxiv_id, '0123456789')) - 1)
+ WHERE rtrim(arxiv_id, '0123456789') LIKE '%v' AND rtrim(arxiv_id, '0123456789') <> arxiv_id;
+
+-- これまでにバージョン違いで別々に保存された論文があり得るため、UNIQUEにはしない
+CREATE INDEX papers_arxiv_id ON papers (arxiv_id);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- コメントやjournal_refから取り出した投稿先
+CREATE TABLE paper_venues
+(
+    paper_id INTEGER NOT NULL PRIMARY KEY,
+    name     TEXT    NOT NULL,
+    year     INTEGER,
+    track    TEXT,
+    status   TEXT    NOT NULL,
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- アブストラクトやコメントに書かれたコード・プロジェクトページ・データセットのURL
+CREATE TABLE paper_links
+(
+    paper_id INTEGER NOT NULL,
+    kind     TEXT    NOT NULL,
+    url      TEXT    NOT NULL,
+    PRIMARY KEY (paper_id, url),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- コメントに書かれたページ数・図の数・表の数
+CREATE TABLE paper_counts
+(
+    paper_id INTEGER NOT NULL PRIMARY KEY,
+    pages    INTEGER,
+    figures  INTEGER,
+    tables   INTEGER,
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- ダウンロードしたPDF。pathはライブラリのディレクトリからの相対パスで、同じ内容のPDFは同じファイルになる
+CREATE TABLE paper_files
+(
+    paper_id INTEGER  NOT NULL,
+    version  INTEGER  NOT NULL,
+    path     TEXT     NOT NULL,
+    size     INTEGER  NOT NULL,
+    sha256   TEXT     NOT NULL,
+    created  DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (paper_id, version),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
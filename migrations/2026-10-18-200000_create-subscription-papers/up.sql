@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- 購読ごとに保存した論文。slackの通知はwebhookごとにまとめるので、同じwebhookの購読を区別するのに使う
+-- 既存の論文はどの購読で保存したか分からないので記録しない(backfillしない)。このマイグレーションの後に保存した論文から記録する
+CREATE TABLE subscription_papers
+(
+    subscription TEXT     NOT NULL,
+    paper_id     INTEGER  NOT NULL,
+    created      DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (subscription, paper_id),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
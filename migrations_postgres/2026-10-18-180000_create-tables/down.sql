@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE paper_counts;
+DROP TABLE paper_links;
+DROP TABLE paper_venues;
+DROP TABLE paper_versions;
+DROP TABLE paper_classes;
+DROP TABLE paper_affiliations;
+DROP TABLE paper_categories;
+DROP TABLE oai_harvests;
+DROP TABLE subscription_state;
+DROP TABLE slack_notifications;
+DROP TABLE paper_authors;
+DROP TABLE papers;
+DROP TABLE categories;
+DROP TABLE authors;
@@ -0,0 +1,140 @@
+-- Your SQL goes here
+-- migrations/の2026-10-18-170000_create-paper-countsまでを適用したSQLiteのDBと同じテーブル
+CREATE TABLE authors
+(
+    id   SERIAL NOT NULL PRIMARY KEY,
+    name TEXT   NOT NULL UNIQUE
+);
+
+CREATE TABLE categories
+(
+    id   SERIAL      NOT NULL PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE papers
+(
+    id          SERIAL    NOT NULL PRIMARY KEY,
+    title       TEXT      NOT NULL,
+    url         TEXT      NOT NULL UNIQUE,
+    pdf_url     TEXT      NOT NULL UNIQUE,
+    category_id INTEGER   NOT NULL,
+    summary     TEXT      NOT NULL,
+    comment     TEXT      NOT NULL,
+    accepted    INTEGER   NOT NULL,
+    updated     TIMESTAMP NOT NULL,
+    published   TIMESTAMP NOT NULL,
+    created     TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    journal_ref TEXT,
+    doi         TEXT,
+    -- バージョンを除いたarXiv ID
+    arxiv_id    TEXT,
+    FOREIGN KEY (category_id) REFERENCES categories (id)
+);
+
+CREATE INDEX arxiv_papers_category_id ON papers (category_id);
+CREATE INDEX papers_arxiv_id ON papers (arxiv_id);
+
+CREATE TABLE paper_authors
+(
+    paper_id  INTEGER NOT NULL,
+    author_id INTEGER NOT NULL,
+    PRIMARY KEY (paper_id, author_id),
+    FOREIGN KEY (paper_id) REFERENCES papers (id),
+    FOREIGN KEY (author_id) REFERENCES authors (id)
+);
+
+CREATE TABLE slack_notifications
+(
+    id          SERIAL      NOT NULL PRIMARY KEY,
+    paper_id    INTEGER     NOT NULL,
+    slack_url   TEXT        NOT NULL,
+    updated_at  TIMESTAMP   NOT NULL,
+    send        BOOLEAN     NOT NULL DEFAULT FALSE,
+    created     TIMESTAMP   NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
+
+CREATE INDEX not_yet_slack_notifications ON slack_notifications(slack_url, send);
+CREATE UNIQUE INDEX slack_notification_paper ON slack_notifications(slack_url, paper_id, updated_at);
+
+CREATE TABLE subscription_state
+(
+    subscription TEXT      NOT NULL PRIMARY KEY,
+    last_updated TIMESTAMP NOT NULL,
+    created      TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+CREATE TABLE oai_harvests
+(
+    harvest          TEXT      NOT NULL PRIMARY KEY,
+    resumption_token TEXT,
+    updated          TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+CREATE TABLE paper_categories
+(
+    paper_id    INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    PRIMARY KEY (paper_id, category_id),
+    FOREIGN KEY (paper_id) REFERENCES papers (id),
+    FOREIGN KEY (category_id) REFERENCES categories (id)
+);
+
+CREATE TABLE paper_affiliations
+(
+    paper_id  INTEGER NOT NULL,
+    author_id INTEGER NOT NULL,
+    name      TEXT    NOT NULL,
+    PRIMARY KEY (paper_id, author_id, name),
+    FOREIGN KEY (paper_id) REFERENCES papers (id),
+    FOREIGN KEY (author_id) REFERENCES authors (id)
+);
+
+CREATE TABLE paper_classes
+(
+    paper_id INTEGER NOT NULL,
+    name     TEXT    NOT NULL,
+    PRIMARY KEY (paper_id, name),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
+
+CREATE TABLE paper_versions
+(
+    paper_id INTEGER   NOT NULL,
+    version  INTEGER   NOT NULL,
+    title    TEXT      NOT NULL,
+    summary  TEXT      NOT NULL,
+    comment  TEXT      NOT NULL,
+    updated  TIMESTAMP NOT NULL,
+    PRIMARY KEY (paper_id, version),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
+
+CREATE TABLE paper_venues
+(
+    paper_id INTEGER NOT NULL PRIMARY KEY,
+    name     TEXT    NOT NULL,
+    year     INTEGER,
+    track    TEXT,
+    status   TEXT    NOT NULL,
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
+
+CREATE TABLE paper_links
+(
+    paper_id INTEGER NOT NULL,
+    kind     TEXT    NOT NULL,
+    url      TEXT    NOT NULL,
+    PRIMARY KEY (paper_id, url),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
+
+CREATE TABLE paper_counts
+(
+    paper_id INTEGER NOT NULL PRIMARY KEY,
+    pages    INTEGER,
+    figures  INTEGER,
+    tables   INTEGER,
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
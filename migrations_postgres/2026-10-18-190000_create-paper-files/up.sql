@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- migrations/の2026-10-18-190000_create-paper-filesと同じテーブル
+CREATE TABLE paper_files
+(
+    paper_id INTEGER   NOT NULL,
+    version  INTEGER   NOT NULL,
+    path     TEXT      NOT NULL,
+    size     BIGINT    NOT NULL,
+    sha256   TEXT      NOT NULL,
+    created  TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    PRIMARY KEY (paper_id, version),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
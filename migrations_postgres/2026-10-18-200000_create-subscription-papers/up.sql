@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- migrations/の2026-10-18-200000_create-subscription-papersと同じテーブル
+-- 既存の論文はどの購読で保存したか分からないので記録しない(backfillしない)。このマイグレーションの後に保存した論文から記録する
+CREATE TABLE subscription_papers
+(
+    subscription TEXT      NOT NULL,
+    paper_id     INTEGER   NOT NULL,
+    created      TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    PRIMARY KEY (subscription, paper_id),
+    FOREIGN KEY (paper_id) REFERENCES papers (id)
+);
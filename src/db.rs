@@ -1,3 +1,4 @@
+pub mod migrations;
 pub mod schema;
 pub mod models;
 
@@ -0,0 +1,99 @@
+use diesel_migrations::MigrationConnection;
+use std::collections::HashSet;
+use std::io::Write;
+
+use crate::infrastructure::database::Database;
+
+// migrations/のup.sqlをバイナリに埋め込み、diesel_cliなしでDBを作れるようにする
+mod sqlite {
+    #[derive(EmbedMigrations)]
+    #[embed_migrations_options(migrations_path = "migrations")]
+    struct _Dummy;
+
+    pub fn versions() -> Vec<&'static str> {
+        ALL_MIGRATIONS.iter().map(|m| m.version()).collect()
+    }
+}
+
+// PostgreSQL用のDDL。migrations/にマイグレーションを追加したときは、こちらにも同じ変更を加える
+#[cfg(feature = "postgres")]
+mod postgres {
+    #[derive(EmbedMigrations)]
+    #[embed_migrations_options(migrations_path = "migrations_postgres")]
+    struct _Dummy;
+
+    pub fn versions() -> Vec<&'static str> {
+        ALL_MIGRATIONS.iter().map(|m| m.version()).collect()
+    }
+}
+
+fn versions(db: &Database) -> Vec<&'static str> {
+    match db {
+        Database::Sqlite(_) => sqlite::versions(),
+        #[cfg(feature = "postgres")]
+        Database::Postgres(_) => postgres::versions(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MigrationStatus {
+    pub version: String,
+    pub applied: bool,
+    // DBには適用済みだがこのバイナリに含まれていない（より新しいバイナリで適用された）マイグレーション
+    pub unknown: bool,
+}
+
+// 未適用のマイグレーションを古い順に適用し、適用したものをoutに書き出す
+pub fn run(db: &Database, out: &mut dyn Write) -> anyhow::Result<()> {
+    match db {
+        Database::Sqlite(conn) => sqlite::run_with_output(conn, out)?,
+        #[cfg(feature = "postgres")]
+        Database::Postgres(conn) => postgres::run_with_output(conn, out)?,
+    }
+    Ok(())
+}
+
+pub fn status(db: &Database) -> anyhow::Result<Vec<MigrationStatus>> {
+    let applied = match db {
+        Database::Sqlite(conn) => previously_run_migration_versions(conn)?,
+        #[cfg(feature = "postgres")]
+        Database::Postgres(conn) => previously_run_migration_versions(conn)?,
+    };
+    let versions = versions(db);
+    let mut statuses: Vec<MigrationStatus> = versions.iter()
+        .map(|v| MigrationStatus {
+            version: v.to_string(),
+            applied: applied.contains(*v),
+            unknown: false,
+        })
+        .collect();
+    statuses.extend(applied.iter()
+        .filter(|v| !versions.contains(&v.as_str()))
+        .map(|v| MigrationStatus { version: v.to_owned(), applied: true, unknown: true }));
+    statuses.sort_by(|a, b| a.version.cmp(&b.version));
+    Ok(statuses)
+}
+
+fn previously_run_migration_versions<C: MigrationConnection>(conn: &C) -> anyhow::Result<HashSet<String>> {
+    diesel_migrations::setup_database(conn)?;
+    Ok(conn.previously_run_migration_versions()?)
+}
+
+#[test]
+fn test_migrations() {
+    use crate::infrastructure::database::DatabaseUrl;
+    let conn = DatabaseUrl::new(":memory:").connect().unwrap();
+    assert!(status(&conn).unwrap().iter().all(|s| !s.applied));
+
+    let mut out = vec![];
+    run(&conn, &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("Running migration"));
+    let statuses = status(&conn).unwrap();
+    assert_eq!(statuses.len(), versions(&conn).len());
+    assert!(statuses.iter().all(|s| s.applied && !s.unknown));
+
+    // 二回目は何も適用しない
+    let mut out = vec![];
+    run(&conn, &mut out).unwrap();
+    assert!(out.is_empty());
+}
@@ -20,6 +20,24 @@ pub struct Category {
     pub name: String,
 }
 
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(harvest)]
+#[table_name = "oai_harvests"]
+pub struct OaiHarvest {
+    pub harvest: String,
+    pub resumption_token: Option<String>,
+    pub updated: NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id, author_id, name)]
+#[table_name = "paper_affiliations"]
+pub struct PaperAffiliation {
+    pub paper_id: i32,
+    pub author_id: i32,
+    pub name: String,
+}
+
 #[derive(Queryable, Debug, Identifiable)]
 #[primary_key(paper_id, author_id)]
 #[table_name = "paper_authors"]
@@ -28,6 +46,76 @@ pub struct PaperAuthor {
     pub author_id: i32,
 }
 
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id, category_id)]
+#[table_name = "paper_categories"]
+pub struct PaperCategory {
+    pub paper_id: i32,
+    pub category_id: i32,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id, name)]
+#[table_name = "paper_classes"]
+pub struct PaperClass {
+    pub paper_id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id)]
+#[table_name = "paper_counts"]
+pub struct PaperCount {
+    pub paper_id: i32,
+    pub pages: Option<i32>,
+    pub figures: Option<i32>,
+    pub tables: Option<i32>,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id, version)]
+#[table_name = "paper_files"]
+pub struct PaperFile {
+    pub paper_id: i32,
+    pub version: i32,
+    pub path: String,
+    pub size: i64,
+    pub sha256: String,
+    pub created: NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id, url)]
+#[table_name = "paper_links"]
+pub struct PaperLink {
+    pub paper_id: i32,
+    pub kind: String,
+    pub url: String,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id)]
+#[table_name = "paper_venues"]
+pub struct PaperVenue {
+    pub paper_id: i32,
+    pub name: String,
+    pub year: Option<i32>,
+    pub track: Option<String>,
+    pub status: String,
+}
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(paper_id, version)]
+#[table_name = "paper_versions"]
+pub struct PaperVersion {
+    pub paper_id: i32,
+    pub version: i32,
+    pub title: String,
+    pub summary: String,
+    pub comment: String,
+    pub updated: NaiveDateTime,
+}
+
 #[derive(Queryable, Debug, Identifiable)]
 #[table_name = "papers"]
 pub struct Paper {
@@ -42,6 +130,9 @@ pub struct Paper {
     pub updated: NaiveDateTime,
     pub published: NaiveDateTime,
     pub created: NaiveDateTime,
+    pub journal_ref: Option<String>,
+    pub doi: Option<String>,
+    pub arxiv_id: Option<String>,
 }
 
 #[derive(Queryable, Debug, Identifiable)]
@@ -55,3 +146,12 @@ pub struct SlackNotification {
     pub created: NaiveDateTime,
 }
 
+
+#[derive(Queryable, Debug, Identifiable)]
+#[primary_key(subscription)]
+#[table_name = "subscription_state"]
+pub struct SubscriptionState {
+    pub subscription: String,
+    pub last_updated: NaiveDateTime,
+    pub created: NaiveDateTime,
+}
@@ -12,6 +12,22 @@ table! {
     }
 }
 
+table! {
+    oai_harvests (harvest) {
+        harvest -> Text,
+        resumption_token -> Nullable<Text>,
+        updated -> Timestamp,
+    }
+}
+
+table! {
+    paper_affiliations (paper_id, author_id, name) {
+        paper_id -> Integer,
+        author_id -> Integer,
+        name -> Text,
+    }
+}
+
 table! {
     paper_authors (paper_id, author_id) {
         paper_id -> Integer,
@@ -19,6 +35,69 @@ table! {
     }
 }
 
+table! {
+    paper_categories (paper_id, category_id) {
+        paper_id -> Integer,
+        category_id -> Integer,
+    }
+}
+
+table! {
+    paper_classes (paper_id, name) {
+        paper_id -> Integer,
+        name -> Text,
+    }
+}
+
+table! {
+    paper_counts (paper_id) {
+        paper_id -> Integer,
+        pages -> Nullable<Integer>,
+        figures -> Nullable<Integer>,
+        tables -> Nullable<Integer>,
+    }
+}
+
+table! {
+    paper_files (paper_id, version) {
+        paper_id -> Integer,
+        version -> Integer,
+        path -> Text,
+        size -> BigInt,
+        sha256 -> Text,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    paper_links (paper_id, url) {
+        paper_id -> Integer,
+        kind -> Text,
+        url -> Text,
+    }
+}
+
+table! {
+    paper_venues (paper_id) {
+        paper_id -> Integer,
+        name -> Text,
+        year -> Nullable<Integer>,
+        track -> Nullable<Text>,
+        status -> Text,
+    }
+}
+
+table! {
+    paper_versions (paper_id, version) {
+        paper_id -> Integer,
+        version -> Integer,
+        title -> Text,
+        summary -> Text,
+        comment -> Text,
+        updated -> Timestamp,
+    }
+}
+
 table! {
     papers (id) {
         id -> Integer,
@@ -32,6 +111,9 @@ table! {
         updated -> Timestamp,
         published -> Timestamp,
         created -> Timestamp,
+        journal_ref -> Nullable<Text>,
+        doi -> Nullable<Text>,
+        arxiv_id -> Nullable<Text>,
     }
 }
 
@@ -46,15 +128,53 @@ table! {
     }
 }
 
+table! {
+    subscription_papers (subscription, paper_id) {
+        subscription -> Text,
+        paper_id -> Integer,
+        created -> Timestamp,
+    }
+}
+
+table! {
+    subscription_state (subscription) {
+        subscription -> Text,
+        last_updated -> Timestamp,
+        created -> Timestamp,
+    }
+}
+
+joinable!(paper_affiliations -> authors (author_id));
+joinable!(paper_affiliations -> papers (paper_id));
 joinable!(paper_authors -> authors (author_id));
 joinable!(paper_authors -> papers (paper_id));
+joinable!(paper_categories -> categories (category_id));
+joinable!(paper_categories -> papers (paper_id));
+joinable!(paper_classes -> papers (paper_id));
+joinable!(paper_counts -> papers (paper_id));
+joinable!(paper_files -> papers (paper_id));
+joinable!(paper_links -> papers (paper_id));
+joinable!(paper_venues -> papers (paper_id));
+joinable!(paper_versions -> papers (paper_id));
 joinable!(papers -> categories (category_id));
 joinable!(slack_notifications -> papers (paper_id));
+joinable!(subscription_papers -> papers (paper_id));
 
 allow_tables_to_appear_in_same_query!(
     authors,
     categories,
+    oai_harvests,
+    paper_affiliations,
     paper_authors,
+    paper_categories,
+    paper_classes,
+    paper_counts,
+    paper_files,
+    paper_links,
+    paper_venues,
+    paper_versions,
     papers,
     slack_notifications,
+    subscription_papers,
+    subscription_state,
 );
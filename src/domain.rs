@@ -1,4 +1,13 @@
+pub mod author_name;
+pub mod arxiv_id;
 pub mod arxiv_paper;
 pub mod arxiv_api;
 pub mod slack_api;
-pub mod slack_notification;
\ No newline at end of file
+pub mod slack_notification;
+pub mod latex;
+pub mod oai_harvest;
+pub mod paper_counts;
+pub mod paper_link;
+pub mod subscription_state;
+pub mod text_diff;
+pub mod venue;
\ No newline at end of file
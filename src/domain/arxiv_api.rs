@@ -1,5 +1,11 @@
 use chrono::NaiveDateTime;
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Affiliation {
+    pub author: String,
+    pub name: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct Paper {
     pub title: String,
@@ -7,9 +13,16 @@ pub struct Paper {
     pub pdf_url: String,
     pub authors: Vec<String>,
     pub category: String,
+    // メインカテゴリを含む全てのカテゴリ（クロスリスト先も含む）
+    pub categories: Vec<String>,
     pub summary: String,
     pub comment: String,
     pub is_accepted: bool,
+    pub journal_ref: Option<String>,
+    pub doi: Option<String>,
+    pub affiliations: Vec<Affiliation>,
+    // ACMやMSCの分類 (I.4.8, 68T45)
+    pub classes: Vec<String>,
     pub updated: NaiveDateTime,
     pub published: NaiveDateTime,
 }
@@ -17,3 +30,9 @@ pub struct Paper {
 pub trait ArxivAPITrait {
     fn query(&self) -> anyhow::Result<Vec<Paper>>;
 }
+
+impl<T: ArxivAPITrait + ?Sized> ArxivAPITrait for &T {
+    fn query(&self) -> anyhow::Result<Vec<Paper>> {
+        (**self).query()
+    }
+}
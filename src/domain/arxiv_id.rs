@@ -0,0 +1,64 @@
+use regex::Regex;
+use std::fmt;
+
+// バージョンを除いたarXivの論文ID
+// 新形式 "2104.01234" と旧形式 "hep-th/9901001" の両方を扱う
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArxivId {
+    pub id: String,
+    pub version: Option<u32>,
+}
+
+impl ArxivId {
+    // "2104.01234v2", "arXiv:hep-th/9901001", "http://arxiv.org/abs/2104.01234v2" などを受け付ける
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let re = Regex::new(concat!(
+            r"^(?:https?://(?:export\.|www\.)?arxiv\.org/(?:abs|pdf)/|arXiv:)?",
+            r"(\d{4}\.\d{4,5}|[a-z]+(?:-[a-z]+)*(?:\.[A-Z]{2})?/\d{7})",
+            r"(?:v(\d+))?(?:\.pdf)?$",
+        ))?;
+        let caps = re.captures(s.trim())
+            .ok_or_else(|| anyhow::anyhow!("Invalid arXiv id `{}`", s))?;
+        Ok(Self {
+            id: caps[1].to_string(),
+            version: match caps.get(2) {
+                Some(v) => Some(v.as_str().parse()?),
+                None => None,
+            },
+        })
+    }
+
+    pub fn abs_url(&self) -> String {
+        format!("http://arxiv.org/abs/{}", self)
+    }
+}
+
+impl fmt::Display for ArxivId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.version {
+            Some(v) => write!(f, "{}v{}", self.id, v),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+#[test]
+fn test_arxiv_id() {
+    let id = ArxivId::parse("http://arxiv.org/abs/2104.01234v2").unwrap();
+    assert_eq!(id, ArxivId { id: "2104.01234".to_string(), version: Some(2) });
+    assert_eq!(id.to_string(), "2104.01234v2");
+    assert_eq!(id.abs_url(), "http://arxiv.org/abs/2104.01234v2");
+
+    let id = ArxivId::parse("arXiv:0704.0001").unwrap();
+    assert_eq!(id, ArxivId { id: "0704.0001".to_string(), version: None });
+
+    let id = ArxivId::parse("https://arxiv.org/pdf/hep-th/9901001v3.pdf").unwrap();
+    assert_eq!(id, ArxivId { id: "hep-th/9901001".to_string(), version: Some(3) });
+    let id = ArxivId::parse("math.AG/0101001").unwrap();
+    assert_eq!(id.id, "math.AG/0101001");
+    let id = ArxivId::parse("solv-int/9901001v1").unwrap();
+    assert_eq!(id.id, "solv-int/9901001");
+
+    assert!(ArxivId::parse("http://example.com/abs/2104.01234").is_err());
+    assert!(ArxivId::parse("2104.012").is_err());
+}
@@ -1,4 +1,8 @@
 use super::arxiv_api as API;
+use super::arxiv_id::ArxivId;
+use super::paper_counts::PaperCounts;
+use super::paper_link::{extract_links, PaperLink};
+use super::venue::Venue;
 use std::convert::From;
 use chrono::NaiveDateTime;
 
@@ -6,6 +10,8 @@ use chrono::NaiveDateTime;
 pub struct Author {
     pub id: i32,
     pub name: String,
+    // この論文での所属
+    pub affiliations: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -14,27 +20,75 @@ pub struct Category {
     pub name: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PaperId(pub i32);
 
+// 保存済みの各バージョンの内容
+#[derive(Debug, Clone)]
+pub struct PaperVersion {
+    pub version: u32,
+    pub title: String,
+    pub summary: String,
+    pub comment: String,
+    pub is_accepted: bool,
+    pub updated: NaiveDateTime,
+}
+
 #[derive(Debug, Clone)]
 pub struct Paper {
     pub id: PaperId,
+    pub arxiv_id: Option<ArxivId>,
     pub title: String,
     pub url: String,
     pub pdf_url: String,
     pub authors: Vec<Author>,
     pub category: Category,
+    pub categories: Vec<Category>,
     pub summary: String,
     pub comment: String,
     pub is_accepted: bool,
+    pub journal_ref: Option<String>,
+    pub doi: Option<String>,
+    pub classes: Vec<String>,
+    pub venue: Option<Venue>,
+    pub links: Vec<PaperLink>,
+    pub counts: PaperCounts,
+    // 古い順
+    pub versions: Vec<PaperVersion>,
     pub updated: NaiveDateTime,
     pub published: NaiveDateTime,
 }
 
+// ダウンロードした各バージョンのPDF。pathはライブラリのディレクトリからの相対パス
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperFile {
+    pub paper_id: PaperId,
+    pub version: u32,
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Paper {
+    // 保存している最新のバージョン。URLにバージョンがない場合は記録にある最新のバージョン
+    pub fn current_version(&self) -> Option<u32> {
+        self.arxiv_id.as_ref().and_then(|id| id.version)
+            .or_else(|| self.versions.iter().map(|v| v.version).max())
+    }
+
+    // 現在のバージョンの一つ前。バージョンが分からない場合や記録がない場合はNone
+    pub fn previous_version(&self) -> Option<&PaperVersion> {
+        let current = self.arxiv_id.as_ref()?.version?;
+        self.versions.iter()
+            .filter(|v| v.version < current)
+            .max_by_key(|v| v.version)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NewAuthor {
     pub name: String,
+    pub affiliations: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,33 +98,62 @@ pub struct NewCategory {
 
 #[derive(Debug, Clone)]
 pub struct NewPaper {
+    // arXiv以外のURLの場合はNone
+    pub arxiv_id: Option<ArxivId>,
     pub title: String,
     pub url: String,
     pub pdf_url: String,
     pub category: NewCategory,
+    pub categories: Vec<NewCategory>,
     pub authors: Vec<NewAuthor>,
     pub summary: String,
     pub updated: NaiveDateTime,
     pub published: NaiveDateTime,
     pub comment: String,
     pub is_accepted: bool,
+    pub journal_ref: Option<String>,
+    pub doi: Option<String>,
+    pub classes: Vec<String>,
+    // 会議名の表記揺れのリストが必要なので、変換後にVenueExtractorで埋める
+    pub venue: Option<Venue>,
+    pub links: Vec<PaperLink>,
+    pub counts: PaperCounts,
 }
 
 impl From<API::Paper> for NewPaper {
     fn from(paper: API::Paper) -> Self {
+        let authors = paper.authors.iter()
+            .map(|a| NewAuthor {
+                name: a.to_owned(),
+                affiliations: paper.affiliations.iter()
+                    .filter(|af| &af.author == a)
+                    .map(|af| af.name.to_owned())
+                    .collect(),
+            })
+            .collect();
+        let links = extract_links(&format!("{}\n{}", paper.summary, paper.comment));
+        let counts = PaperCounts::parse(&paper.comment);
         Self {
+            arxiv_id: ArxivId::parse(&paper.url).ok(),
             title: paper.title,
             updated: paper.updated,
             published: paper.published,
             url: paper.url,
             pdf_url: paper.pdf_url,
             category: NewCategory { name: paper.category },
-            authors: paper.authors.iter()
-                .map(|a| NewAuthor { name: a.to_owned() })
+            categories: paper.categories.iter()
+                .map(|c| NewCategory { name: c.to_owned() })
                 .collect(),
+            authors,
             summary: paper.summary,
             comment: paper.comment,
             is_accepted: paper.is_accepted,
+            journal_ref: paper.journal_ref,
+            doi: paper.doi,
+            classes: paper.classes,
+            venue: None,
+            links,
+            counts,
         }
     }
 }
@@ -78,6 +161,41 @@ impl From<API::Paper> for NewPaper {
 pub trait ArxivPaperRepositoryTrait {
     fn find_by_id(&self, id: PaperId) -> anyhow::Result<Option<Paper>>;
     fn find_by_urls(&self, urls: &Vec<String>) -> anyhow::Result<Vec<Paper>>;
+    fn find_by_ids(&self, ids: &[PaperId]) -> anyhow::Result<Vec<Paper>>;
+    // バージョンを除いたarXiv IDで探す。保存済みのバージョンによらず見つかる
+    fn find_by_arxiv_ids(&self, arxiv_ids: &[String]) -> anyhow::Result<Vec<Paper>>;
     fn save(&self, papers: &Vec<NewPaper>) -> anyhow::Result<usize>;
-    fn download(&self, papers: &Vec<Paper>) -> bool;
+    fn find_files(&self, ids: &[PaperId]) -> anyhow::Result<Vec<PaperFile>>;
+    // 保存済みの著者名のうち、表記揺れを吸収してフォローしている著者と一致するもの
+    fn find_author_names(&self, follow_authors: &[String]) -> anyhow::Result<Vec<String>>;
+    // 現在のバージョンのPDFをダウンロードし、新たにダウンロードしたファイルを返す
+    fn download(&self, papers: &Vec<Paper>) -> anyhow::Result<Vec<PaperFile>>;
+}
+
+// DBとインメモリのリポジトリを実行時に選べるように、&dyn ArxivPaperRepositoryTraitとしても使えるようにする
+impl<T: ArxivPaperRepositoryTrait + ?Sized> ArxivPaperRepositoryTrait for &T {
+    fn find_by_id(&self, id: PaperId) -> anyhow::Result<Option<Paper>> {
+        (**self).find_by_id(id)
+    }
+    fn find_by_urls(&self, urls: &Vec<String>) -> anyhow::Result<Vec<Paper>> {
+        (**self).find_by_urls(urls)
+    }
+    fn find_by_ids(&self, ids: &[PaperId]) -> anyhow::Result<Vec<Paper>> {
+        (**self).find_by_ids(ids)
+    }
+    fn find_by_arxiv_ids(&self, arxiv_ids: &[String]) -> anyhow::Result<Vec<Paper>> {
+        (**self).find_by_arxiv_ids(arxiv_ids)
+    }
+    fn save(&self, papers: &Vec<NewPaper>) -> anyhow::Result<usize> {
+        (**self).save(papers)
+    }
+    fn find_files(&self, ids: &[PaperId]) -> anyhow::Result<Vec<PaperFile>> {
+        (**self).find_files(ids)
+    }
+    fn find_author_names(&self, follow_authors: &[String]) -> anyhow::Result<Vec<String>> {
+        (**self).find_author_names(follow_authors)
+    }
+    fn download(&self, papers: &Vec<Paper>) -> anyhow::Result<Vec<PaperFile>> {
+        (**self).download(papers)
+    }
 }
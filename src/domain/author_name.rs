@@ -0,0 +1,75 @@
+use unicode_normalization::UnicodeNormalization;
+
+// 表記揺れを吸収した著者名
+// "Taro Yamada", "Yamada, Taro", "T. Yamada", "Tarō Yamada" を同じ著者として扱う
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorName {
+    pub last: String,
+    pub initials: String,
+}
+
+fn fold(s: &str) -> String {
+    // アクセント記号を取り除き、英数字以外は空白にする
+    s.nfd()
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect()
+}
+
+impl AuthorName {
+    pub fn parse(name: &str) -> Self {
+        let (first, last) = match name.find(',') {
+            Some(i) => (fold(&name[i + 1..]), fold(&name[..i])),
+            None => {
+                let folded = fold(name);
+                let mut words: Vec<&str> = folded.split_whitespace().collect();
+                let last = words.pop().unwrap_or("").to_string();
+                (words.join(" "), last)
+            }
+        };
+        Self {
+            last: last.split_whitespace().collect::<Vec<&str>>().join(" "),
+            initials: first.split_whitespace().filter_map(|w| w.chars().next()).collect(),
+        }
+    }
+
+    fn same_last_name(&self, other: &AuthorName) -> bool {
+        // "Adrian Del Maestro" と "Del Maestro, Adrian" のように姓の区切り位置が違う場合がある
+        self.last == other.last
+            || self.last.ends_with(&format!(" {}", other.last))
+            || other.last.ends_with(&format!(" {}", self.last))
+    }
+
+    // イニシャルは片方が省略されている場合もあるので先頭の一文字だけ比べる
+    pub fn matches(&self, other: &AuthorName) -> bool {
+        if self.last.is_empty() || !self.same_last_name(other) {
+            return false;
+        }
+        match (self.initials.chars().next(), other.initials.chars().next()) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+}
+
+pub fn find_followed<'a>(authors: &'a [String], follow_authors: &[String]) -> Vec<&'a String> {
+    let follow_authors: Vec<AuthorName> = follow_authors.iter().map(|a| AuthorName::parse(a)).collect();
+    authors.iter()
+        .filter(|a| {
+            let name = AuthorName::parse(a);
+            follow_authors.iter().any(|f| f.matches(&name))
+        })
+        .collect()
+}
+
+#[test]
+fn test_author_name() {
+    let yamada = AuthorName::parse("Taro Yamada");
+    assert_eq!(yamada, AuthorName { last: "yamada".to_string(), initials: "t".to_string() });
+    assert!(yamada.matches(&AuthorName::parse("Yamada, Taro")));
+    assert!(yamada.matches(&AuthorName::parse("T. Yamada")));
+    assert!(yamada.matches(&AuthorName::parse("Tarō Yamáda")));
+    assert!(!yamada.matches(&AuthorName::parse("Hanako Yamada")));
+    assert!(!yamada.matches(&AuthorName::parse("Taro Tanaka")));
+    assert!(AuthorName::parse("Adrian Del Maestro").matches(&AuthorName::parse("Del Maestro, A.")));
+}
@@ -0,0 +1,361 @@
+use unicode_normalization::UnicodeNormalization;
+
+// タイトルやアブストラクトに書かれたLaTeXを表示・検索用にUnicodeの文字列にする
+// DBにはarXivから取得したままの文字列を保存し、表示と検索のときだけこれを通す
+
+const GREEK: &[(&str, &str)] = &[
+    ("alpha", "α"), ("beta", "β"), ("gamma", "γ"), ("delta", "δ"), ("epsilon", "ϵ"), ("varepsilon", "ε"),
+    ("zeta", "ζ"), ("eta", "η"), ("theta", "θ"), ("vartheta", "ϑ"), ("iota", "ι"), ("kappa", "κ"),
+    ("lambda", "λ"), ("mu", "μ"), ("nu", "ν"), ("xi", "ξ"), ("pi", "π"), ("varpi", "ϖ"), ("rho", "ρ"),
+    ("varrho", "ϱ"), ("sigma", "σ"), ("varsigma", "ς"), ("tau", "τ"), ("upsilon", "υ"), ("phi", "ϕ"),
+    ("varphi", "φ"), ("chi", "χ"), ("psi", "ψ"), ("omega", "ω"),
+    ("Gamma", "Γ"), ("Delta", "Δ"), ("Theta", "Θ"), ("Lambda", "Λ"), ("Xi", "Ξ"), ("Pi", "Π"),
+    ("Sigma", "Σ"), ("Upsilon", "Υ"), ("Phi", "Φ"), ("Psi", "Ψ"), ("Omega", "Ω"),
+];
+
+const SYMBOLS: &[(&str, &str)] = &[
+    ("times", "×"), ("cdot", "·"), ("div", "÷"), ("pm", "±"), ("mp", "∓"), ("ast", "∗"), ("star", "⋆"),
+    ("circ", "∘"), ("bullet", "•"), ("oplus", "⊕"), ("otimes", "⊗"), ("odot", "⊙"),
+    ("leq", "≤"), ("le", "≤"), ("geq", "≥"), ("ge", "≥"), ("neq", "≠"), ("ne", "≠"), ("ll", "≪"), ("gg", "≫"),
+    ("approx", "≈"), ("sim", "∼"), ("simeq", "≃"), ("cong", "≅"), ("equiv", "≡"), ("propto", "∝"),
+    ("to", "→"), ("rightarrow", "→"), ("leftarrow", "←"), ("gets", "←"), ("leftrightarrow", "↔"),
+    ("Rightarrow", "⇒"), ("Leftarrow", "⇐"), ("Leftrightarrow", "⇔"), ("mapsto", "↦"), ("implies", "⟹"), ("iff", "⟺"),
+    ("uparrow", "↑"), ("downarrow", "↓"),
+    ("in", "∈"), ("notin", "∉"), ("ni", "∋"), ("subset", "⊂"), ("subseteq", "⊆"), ("supset", "⊃"), ("supseteq", "⊇"),
+    ("cup", "∪"), ("cap", "∩"), ("setminus", "∖"), ("emptyset", "∅"), ("varnothing", "∅"),
+    ("forall", "∀"), ("exists", "∃"), ("neg", "¬"), ("lnot", "¬"), ("land", "∧"), ("wedge", "∧"), ("lor", "∨"), ("vee", "∨"),
+    ("partial", "∂"), ("nabla", "∇"), ("infty", "∞"), ("sum", "∑"), ("prod", "∏"), ("int", "∫"), ("oint", "∮"),
+    ("ell", "ℓ"), ("hbar", "ℏ"), ("Re", "ℜ"), ("Im", "ℑ"), ("aleph", "ℵ"),
+    ("ldots", "…"), ("dots", "…"), ("cdots", "⋯"), ("vdots", "⋮"), ("ddots", "⋱"),
+    ("langle", "⟨"), ("rangle", "⟩"), ("lceil", "⌈"), ("rceil", "⌉"), ("lfloor", "⌊"), ("rfloor", "⌋"),
+    ("mid", "|"), ("vert", "|"), ("Vert", "‖"), ("parallel", "∥"), ("perp", "⊥"), ("top", "⊤"), ("bot", "⊥"),
+    ("prime", "′"), ("degree", "°"), ("angle", "∠"), ("triangle", "△"),
+    ("lbrace", "{"), ("rbrace", "}"), ("backslash", "\\"), ("colon", ":"),
+    ("textasciitilde", "~"), ("textbackslash", "\\"), ("textendash", "–"), ("textemdash", "—"),
+];
+
+// 文中で使う文字。LaTeXと同じく直後の空白は区切りとして読み飛ばす
+const LETTERS: &[(&str, &str)] = &[
+    ("ss", "ß"), ("o", "ø"), ("O", "Ø"), ("aa", "å"), ("AA", "Å"), ("ae", "æ"), ("AE", "Æ"),
+    ("oe", "œ"), ("OE", "Œ"), ("l", "ł"), ("L", "Ł"), ("i", "ı"), ("j", "ȷ"),
+];
+
+// 数式中でもそのままの名前で表示する関数
+const FUNCTIONS: &[&str] = &[
+    "log", "ln", "lg", "exp", "sin", "cos", "tan", "sinh", "cosh", "tanh", "arcsin", "arccos", "arctan",
+    "max", "min", "arg", "argmax", "argmin", "lim", "sup", "inf", "det", "dim", "ker", "deg", "gcd", "Pr",
+];
+
+// 中身だけを残す書式のマクロ
+const FORMATTING: &[&str] = &[
+    "emph", "textbf", "textit", "texttt", "textrm", "textsf", "textsc", "textnormal", "textup", "textsl",
+    "text", "mbox", "mathrm", "mathbf", "mathit", "mathsf", "mathtt", "mathnormal", "operatorname",
+    "boldsymbol", "bm", "underline", "uppercase", "lowercase",
+];
+
+// 何も出力しない括弧の大きさ指定など
+const IGNORED: &[&str] = &[
+    "left", "right", "big", "Big", "bigg", "Bigg", "bigl", "bigr", "Bigl", "Bigr", "biggl", "biggr",
+    "displaystyle", "textstyle", "scriptstyle", "limits", "nolimits", "noindent",
+    "rm", "bf", "it", "tt", "sf", "em",
+];
+
+// アクセントと対応する結合文字
+const ACCENTS: &[(&str, char)] = &[
+    ("\"", '\u{308}'), ("'", '\u{301}'), ("`", '\u{300}'), ("^", '\u{302}'), ("~", '\u{303}'),
+    ("=", '\u{304}'), (".", '\u{307}'), ("c", '\u{327}'), ("v", '\u{30C}'), ("u", '\u{306}'),
+    ("H", '\u{30B}'), ("r", '\u{30A}'), ("k", '\u{328}'), ("d", '\u{323}'), ("b", '\u{331}'),
+];
+
+// 数式用のアクセント
+const MATH_ACCENTS: &[(&str, char)] = &[
+    ("hat", '\u{302}'), ("widehat", '\u{302}'), ("bar", '\u{304}'), ("overline", '\u{305}'),
+    ("tilde", '\u{303}'), ("widetilde", '\u{303}'), ("vec", '\u{20D7}'), ("dot", '\u{307}'), ("ddot", '\u{308}'),
+];
+
+const SUPERSCRIPTS: (&str, &str) = (
+    "0123456789+-−=()abcdefghijklmnoprstuvwxyzABDEGHIJKLMNOPRTUVW*′",
+    "⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻⁻⁼⁽⁾ᵃᵇᶜᵈᵉᶠᵍʰⁱʲᵏˡᵐⁿᵒᵖʳˢᵗᵘᵛʷˣʸᶻᴬᴮᴰᴱᴳᴴᴵᴶᴷᴸᴹᴺᴼᴾᴿᵀᵁⱽᵂ*′",
+);
+
+const SUBSCRIPTS: (&str, &str) = (
+    "0123456789+-−=()aehijklmnoprstuvx*",
+    "₀₁₂₃₄₅₆₇₈₉₊₋₋₌₍₎ₐₑₕᵢⱼₖₗₘₙₒₚᵣₛₜᵤᵥₓ*",
+);
+
+fn lookup<'a>(table: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    table.iter().find(|(n, _)| *n == name).map(|(_, s)| *s)
+}
+
+fn translate(text: &str, table: (&str, &str)) -> Option<String> {
+    let from: Vec<char> = table.0.chars().collect();
+    let to: Vec<char> = table.1.chars().collect();
+    text.chars()
+        .map(|c| from.iter().position(|f| *f == c).map(|i| to[i]))
+        .collect()
+}
+
+fn accent(text: &str, name: &str) -> Option<String> {
+    let (_, combining) = ACCENTS.iter().find(|(n, _)| *n == name)?;
+    let mut chars = text.chars();
+    let base = match chars.next() {
+        // \'{\i} のように点のないiにアクセントをつけたものはíにする
+        Some('ı') => 'i',
+        Some(c) => c,
+        None => return Some(combining.to_string()),
+    };
+    // 合成済みの文字があればそちらにする
+    Some(format!("{}{}{}", base, combining, chars.as_str()).nfc().collect())
+}
+
+// 𝒜や𝔸のような文字はUnicodeの飛び飛びの位置にあるので、例外だけ表で持つ
+fn math_alphabet(text: &str, name: &str) -> String {
+    let (upper, lower, exceptions): (u32, Option<u32>, &[(char, char)]) = match name {
+        "mathcal" | "mathscr" => (0x1D49C, None, &[
+            ('B', 'ℬ'), ('E', 'ℰ'), ('F', 'ℱ'), ('H', 'ℋ'), ('I', 'ℐ'), ('L', 'ℒ'), ('M', 'ℳ'), ('R', 'ℛ'),
+        ]),
+        "mathbb" => (0x1D538, None, &[
+            ('C', 'ℂ'), ('H', 'ℍ'), ('N', 'ℕ'), ('P', 'ℙ'), ('Q', 'ℚ'), ('R', 'ℝ'), ('Z', 'ℤ'),
+        ]),
+        _ => (0x1D504, Some(0x1D51E), &[('C', 'ℭ'), ('H', 'ℌ'), ('I', 'ℑ'), ('R', 'ℜ'), ('Z', 'ℨ')]),
+    };
+    text.chars().map(|c| {
+        if let Some((_, e)) = exceptions.iter().find(|(from, _)| *from == c) {
+            return *e;
+        }
+        let code = match (c.is_ascii_uppercase(), c.is_ascii_lowercase(), lower) {
+            (true, _, _) => Some(upper + (c as u32 - 'A' as u32)),
+            (_, true, Some(lower)) => Some(lower + (c as u32 - 'a' as u32)),
+            _ => None,
+        };
+        code.and_then(std::char::from_u32).unwrap_or(c)
+    }).collect()
+}
+
+// "a+b" のような式は括弧で囲む
+fn operand(text: &str) -> String {
+    match text.contains(&[' ', '+', '-', '−', '=', '/', ',', '·', '×'][..]) {
+        true => format!("({})", text),
+        false => text.to_string(),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    // 閉じる$があるときだけ数式として扱う。"$5" のような普通のドル記号はそのまま残す
+    fn closing_dollar(&self, from: usize) -> bool {
+        let mut i = from;
+        while i < self.chars.len() {
+            match self.chars[i] {
+                '\\' => i += 2,
+                '$' => return true,
+                _ => i += 1,
+            }
+        }
+        false
+    }
+
+    // endが来るか文字列が終わるまでを変換する
+    fn parse(&mut self, math: bool, end: Option<char>) -> String {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if Some(c) == end {
+                self.pos += 1;
+                if c == '$' && self.peek() == Some('$') {
+                    self.pos += 1;
+                }
+                return out;
+            }
+            match c {
+                '\\' => {
+                    self.pos += 1;
+                    out.push_str(&self.command(math));
+                }
+                '{' => {
+                    self.pos += 1;
+                    out.push_str(&self.parse(math, Some('}')));
+                }
+                // 対応する{がない}は捨てる
+                '}' => self.pos += 1,
+                '$' if !math => {
+                    let display = self.peek_at(1) == Some('$');
+                    let start = self.pos + if display { 2 } else { 1 };
+                    if self.closing_dollar(start) {
+                        self.pos = start;
+                        out.push_str(&self.parse(true, Some('$')));
+                    } else {
+                        out.push(c);
+                        self.pos += 1;
+                    }
+                }
+                '^' | '_' if math => {
+                    self.pos += 1;
+                    let arg = self.argument(math);
+                    let table = if c == '^' { SUPERSCRIPTS } else { SUBSCRIPTS };
+                    out.push_str(&match translate(&arg, table) {
+                        Some(s) => s,
+                        None if arg.chars().count() == 1 => format!("{}{}", c, arg),
+                        None => format!("{}({})", c, arg),
+                    });
+                }
+                '\'' if math => {
+                    self.pos += 1;
+                    out.push('′');
+                }
+                '`' if !math && self.peek_at(1) == Some('`') => {
+                    self.pos += 2;
+                    out.push('“');
+                }
+                '\'' if !math && self.peek_at(1) == Some('\'') => {
+                    self.pos += 2;
+                    out.push('”');
+                }
+                _ => {
+                    self.pos += 1;
+                    out.push(c);
+                }
+            }
+        }
+        out
+    }
+
+    // コマンドの引数。{...}、コマンド一つ、または一文字
+    fn argument(&mut self, math: bool) -> String {
+        while let Some(' ') = self.peek() {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                self.parse(math, Some('}'))
+            }
+            Some('\\') => {
+                self.pos += 1;
+                self.command(math)
+            }
+            Some(c) => {
+                self.pos += 1;
+                c.to_string()
+            }
+            None => "".to_string(),
+        }
+    }
+
+    // \の直後から読む
+    fn command(&mut self, math: bool) -> String {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return "\\".to_string(),
+        };
+        if !c.is_ascii_alphabetic() {
+            self.pos += 1;
+            return match c {
+                '"' | '\'' | '`' | '^' | '~' | '=' | '.' => {
+                    let arg = self.argument(math);
+                    accent(&arg, &c.to_string()).unwrap_or(arg)
+                }
+                ',' | ';' | ':' | ' ' => " ".to_string(),
+                '!' | '/' => "".to_string(),
+                '\\' => " ".to_string(),
+                _ => c.to_string(),
+            };
+        }
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        if let Some(letter) = lookup(LETTERS, &name) {
+            while let Some(' ') = self.peek() {
+                self.pos += 1;
+            }
+            return letter.to_string();
+        }
+        if let Some(symbol) = lookup(GREEK, &name).or_else(|| lookup(SYMBOLS, &name)) {
+            return symbol.to_string();
+        }
+        if FUNCTIONS.contains(&name.as_str()) {
+            return name;
+        }
+        if IGNORED.contains(&name.as_str()) {
+            while let Some(' ') = self.peek() {
+                self.pos += 1;
+            }
+            return "".to_string();
+        }
+        if FORMATTING.contains(&name.as_str()) {
+            return self.argument(math);
+        }
+        match name.as_str() {
+            "mathcal" | "mathscr" | "mathbb" | "mathfrak" => {
+                let arg = self.argument(math);
+                math_alphabet(&arg, &name)
+            }
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument(math);
+                let denominator = self.argument(math);
+                format!("{}/{}", operand(&numerator), operand(&denominator))
+            }
+            "quad" | "qquad" => " ".to_string(),
+            "sqrt" => format!("√{}", operand(&self.argument(math))),
+            "c" | "v" | "u" | "H" | "r" | "k" | "d" | "b" if !math => {
+                let arg = self.argument(math);
+                accent(&arg, &name).unwrap_or(arg)
+            }
+            _ => match MATH_ACCENTS.iter().find(|(n, _)| *n == name) {
+                Some((_, combining)) => {
+                    let arg = self.argument(math);
+                    match arg.chars().count() {
+                        1 => format!("{}{}", arg, combining),
+                        _ => arg,
+                    }
+                }
+                // 知らないコマンドは引数も含めてそのまま残す
+                None if self.peek() == Some('{') => format!("\\{}{{{}}}", name, self.argument(math)),
+                None => format!("\\{}", name),
+            },
+        }
+    }
+}
+
+pub fn to_unicode(text: &str) -> String {
+    // \(...\) と \[...\] は$...$と同じに扱う
+    let text = text.replace("\\(", "$").replace("\\)", "$").replace("\\[", "$$").replace("\\]", "$$");
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+    parser.parse(false, None)
+}
+
+#[test]
+fn test_to_unicode() {
+    assert_eq!(to_unicode(r"An $\mathcal{O}(n^2)$ algorithm"), "An 𝒪(n²) algorithm");
+    assert_eq!(to_unicode(r"\emph{Fast} and \textbf{robust} face parsing"), "Fast and robust face parsing");
+    assert_eq!(to_unicode(r#"Schr{\"o}dinger and Erd\H{o}s and \'Eric"#), "Schrödinger and Erdős and Éric");
+    assert_eq!(to_unicode(r"Ba\v{s}i\'{c} and Mu\~{n}oz, P\'{\i}a"), "Bašić and Muñoz, Pía");
+    assert_eq!(to_unicode(r"$\alpha$-divergence with $\lambda \leq 10^{-3}$"), "α-divergence with λ ≤ 10⁻³");
+    assert_eq!(to_unicode(r"$x_i \in \mathbb{R}^d$, $\frac{1}{\sqrt{n}}$"), "xᵢ ∈ ℝᵈ, 1/√n");
+    assert_eq!(to_unicode(r"$W_{\theta}$ and \(f_{\mathrm{enc}}\)"), "W_θ and f_(enc)");
+    assert_eq!(to_unicode(r"{\em et al.} use $\ell_1$ and $O(n \log n)$"), "et al. use ℓ₁ and O(n log n)");
+    assert_eq!(to_unicode(r"``quoted'' 50\% of \$5"), "“quoted” 50% of $5");
+    // 数式の外の_や~はURLの一部なのでそのまま残す
+    assert_eq!(to_unicode("https://github.com/foo/my_repo and ~user costs $5"), "https://github.com/foo/my_repo and ~user costs $5");
+    assert_eq!(to_unicode(r"\unknown{x} and {\o}re"), r"\unknown{x} and øre");
+
+    for (from, to) in [SUPERSCRIPTS, SUBSCRIPTS].iter() {
+        assert_eq!(from.chars().count(), to.chars().count());
+    }
+}
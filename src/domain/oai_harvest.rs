@@ -0,0 +1,21 @@
+// OAI-PMHで一括取得するときの途中経過
+// resumption_tokenがNoneなら取得は完了している（もしくはまだ始めていない）
+#[derive(Debug, Clone)]
+pub struct OaiHarvest {
+    pub harvest: String,
+    pub resumption_token: Option<String>,
+}
+
+pub trait OaiHarvestRepositoryTrait {
+    fn find(&self, harvest: &str) -> anyhow::Result<Option<OaiHarvest>>;
+    fn save(&self, harvest: &OaiHarvest) -> anyhow::Result<usize>;
+}
+
+impl<T: OaiHarvestRepositoryTrait + ?Sized> OaiHarvestRepositoryTrait for &T {
+    fn find(&self, harvest: &str) -> anyhow::Result<Option<OaiHarvest>> {
+        (**self).find(harvest)
+    }
+    fn save(&self, harvest: &OaiHarvest) -> anyhow::Result<usize> {
+        (**self).save(harvest)
+    }
+}
@@ -0,0 +1,66 @@
+use regex::Regex;
+
+// コメントに書かれたページ数・図の数・表の数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaperCounts {
+    pub pages: Option<u32>,
+    pub figures: Option<u32>,
+    pub tables: Option<u32>,
+}
+
+fn number(word: &str) -> Option<u32> {
+    let words = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve"];
+    match words.iter().position(|w| w.eq_ignore_ascii_case(word)) {
+        Some(i) => Some(i as u32 + 1),
+        None => word.parse().ok(),
+    }
+}
+
+fn find(comment: &str, unit: &str) -> Option<u32> {
+    let re = Regex::new(&format!(
+        r"(?i)\b(\d+|one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve)\s*(?:\+\s*\d+\s*)?(?:{})",
+        unit
+    )).unwrap();
+    re.captures(comment).and_then(|caps| number(&caps[1]))
+}
+
+impl PaperCounts {
+    // "14 pages, 6 figures, 3 tables" や "8+2 pp., one figure" のような表記を読む
+    // "8+2 pages" のように付録を足して書かれている場合は本文のページ数とする
+    pub fn parse(comment: &str) -> Self {
+        Self {
+            pages: find(comment, r"pages?\b|pp\b"),
+            figures: find(comment, r"figures?\b|figs?\b"),
+            tables: find(comment, r"tables?\b"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_none() && self.figures.is_none() && self.tables.is_none()
+    }
+
+    // "14 pages, 6 figures, 3 tables" のような表示用の文字列
+    pub fn label(&self) -> String {
+        let counts = [(self.pages, "page"), (self.figures, "figure"), (self.tables, "table")];
+        counts.iter()
+            .filter_map(|(n, unit)| n.map(|n| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" })))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[test]
+fn test_paper_counts() {
+    let counts = PaperCounts::parse("Accepted to CVPR 2021. 14 pages, 6 figures, 3 tables");
+    assert_eq!(counts, PaperCounts { pages: Some(14), figures: Some(6), tables: Some(3) });
+    assert_eq!(counts.label(), "14 pages, 6 figures, 3 tables");
+
+    let counts = PaperCounts::parse("8+2 pp., one figure");
+    assert_eq!(counts, PaperCounts { pages: Some(8), figures: Some(1), tables: None });
+    assert_eq!(counts.label(), "8 pages, 1 figure");
+
+    let counts = PaperCounts::parse("4 pages; 5 Figs.");
+    assert_eq!(counts, PaperCounts { pages: Some(4), figures: Some(5), tables: None });
+
+    assert!(PaperCounts::parse("Workshop paper, code available").is_empty());
+}
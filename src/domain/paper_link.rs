@@ -0,0 +1,119 @@
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkKind {
+    // GitHub, GitLabのリポジトリやHugging Faceのモデル
+    Code,
+    // プロジェクトページやデモ
+    Project,
+    Dataset,
+}
+
+impl LinkKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "code" => Some(LinkKind::Code),
+            "project" => Some(LinkKind::Project),
+            "dataset" => Some(LinkKind::Dataset),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Code => "code",
+            LinkKind::Project => "project",
+            LinkKind::Dataset => "dataset",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperLink {
+    pub kind: LinkKind,
+    pub url: String,
+}
+
+fn classify(url: &str, before: &str) -> Option<LinkKind> {
+    let host_path = Regex::new(r"^https?://(?:www\.)?([^/]+)(/[^?#]*)?").unwrap();
+    let caps = host_path.captures(url)?;
+    let host = caps[1].to_lowercase();
+    let path: Vec<&str> = caps.get(2).map_or("", |p| p.as_str())
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    match host.as_str() {
+        // ユーザーのページではなくリポジトリのURLだけを対象にする
+        "github.com" | "gitlab.com" | "bitbucket.org" if path.len() >= 2 => Some(LinkKind::Code),
+        "github.com" | "gitlab.com" | "bitbucket.org" => None,
+        "huggingface.co" | "hf.co" => match path.first() {
+            Some(&"datasets") => Some(LinkKind::Dataset),
+            Some(&"spaces") => Some(LinkKind::Project),
+            Some(_) if path.len() >= 2 => Some(LinkKind::Code),
+            _ => None,
+        },
+        h if h.ends_with(".github.io") => Some(LinkKind::Project),
+        // その他のURLは直前に書かれている説明で判断する
+        _ => {
+            let before = before.to_lowercase();
+            if Regex::new(r"(project|web)\s*(page|site)|website|homepage|demo").unwrap().is_match(&before) {
+                Some(LinkKind::Project)
+            } else if before.contains("dataset") || before.contains("benchmark") {
+                Some(LinkKind::Dataset)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// アブストラクトやコメントに書かれたコード・プロジェクトページ・データセットのURL
+pub fn extract_links(text: &str) -> Vec<PaperLink> {
+    // "github.com/foo/bar" のようにスキームが省略されることもある
+    let re = Regex::new(r#"(?i)\b(?:https?://|(?:github\.com|gitlab\.com|huggingface\.co)/)[^\s<>"'(){}\[\]]+"#).unwrap();
+    let mut links: Vec<PaperLink> = vec![];
+    let mut prev_end = 0;
+    for m in re.find_iter(text) {
+        let url = m.as_str().trim_end_matches(|c| ".,;:!?".contains(c));
+        let url = match url.starts_with("http") {
+            true => url.to_string(),
+            false => format!("https://{}", url),
+        };
+        // 説明は同じ文の中で、一つ前のURLより後の数十文字だけを見る
+        let sentence = text[prev_end..m.start()].rfind(&['.', '\n'][..]).map_or(prev_end, |i| prev_end + i + 1);
+        let mut start = sentence.max(m.start().saturating_sub(60));
+        prev_end = m.end();
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        let before = &text[start..m.start()];
+        if let Some(kind) = classify(&url, before) {
+            if !links.iter().any(|l| l.url == url) {
+                links.push(PaperLink { kind, url });
+            }
+        }
+    }
+    links
+}
+
+#[test]
+fn test_extract_links() {
+    let links = extract_links(
+        "We release code at https://github.com/foo/face-parsing. Project page: https://foo.example.com/fp/ \
+         and our dataset is available at https://zenodo.org/record/123, see also https://doi.org/10.1/x",
+    );
+    assert_eq!(links, vec![
+        PaperLink { kind: LinkKind::Code, url: "https://github.com/foo/face-parsing".to_string() },
+        PaperLink { kind: LinkKind::Project, url: "https://foo.example.com/fp/".to_string() },
+        PaperLink { kind: LinkKind::Dataset, url: "https://zenodo.org/record/123".to_string() },
+    ]);
+
+    let links = extract_links(
+        "Code: github.com/bar/baz (https://bar.github.io/baz), models at https://huggingface.co/bar/baz-large \
+         and data at https://huggingface.co/datasets/bar/faces; https://github.com/bar",
+    );
+    assert_eq!(links.iter().map(|l| l.kind.clone()).collect::<Vec<LinkKind>>(), vec![
+        LinkKind::Code, LinkKind::Project, LinkKind::Code, LinkKind::Dataset,
+    ]);
+    assert_eq!(links[0].url, "https://github.com/bar/baz");
+}
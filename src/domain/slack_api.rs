@@ -3,5 +3,14 @@ use crate::domain::arxiv_paper::Paper;
 
 pub trait SlackAPITrait {
     fn send(&self, message: &str);
-    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>) -> Vec<(Paper, String)>;
+    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> Vec<(Paper, String)>;
+}
+
+impl<T: SlackAPITrait + ?Sized> SlackAPITrait for &T {
+    fn send(&self, message: &str) {
+        (**self).send(message)
+    }
+    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> Vec<(Paper, String)> {
+        (**self).build_messages(papers, keywords, follow_authors)
+    }
 }
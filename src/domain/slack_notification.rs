@@ -19,6 +19,26 @@ pub struct NewSlackNotification {
 pub trait SlackNotificationRepositoryTrait {
     fn save(&self, notifications: &Vec<NewSlackNotification>) -> anyhow::Result<usize>;
     fn find_not_send(&self, slack_url: &str) -> anyhow::Result<Vec<Paper>>;
+    // 未送信のものも含め、一度でも通知のキューに入れたことがある論文
+    fn find_notified(&self, slack_url: &str, paper_ids: &[PaperId]) -> anyhow::Result<Vec<PaperId>>;
     fn mark_as_send(&self, slack_url: &str, paper_id: &PaperId) -> anyhow::Result<usize>;
     fn delete(&self, slack_url: &str, paper_id: &PaperId) -> anyhow::Result<()>;
 }
+
+impl<T: SlackNotificationRepositoryTrait + ?Sized> SlackNotificationRepositoryTrait for &T {
+    fn save(&self, notifications: &Vec<NewSlackNotification>) -> anyhow::Result<usize> {
+        (**self).save(notifications)
+    }
+    fn find_not_send(&self, slack_url: &str) -> anyhow::Result<Vec<Paper>> {
+        (**self).find_not_send(slack_url)
+    }
+    fn find_notified(&self, slack_url: &str, paper_ids: &[PaperId]) -> anyhow::Result<Vec<PaperId>> {
+        (**self).find_notified(slack_url, paper_ids)
+    }
+    fn mark_as_send(&self, slack_url: &str, paper_id: &PaperId) -> anyhow::Result<usize> {
+        (**self).mark_as_send(slack_url, paper_id)
+    }
+    fn delete(&self, slack_url: &str, paper_id: &PaperId) -> anyhow::Result<()> {
+        (**self).delete(slack_url, paper_id)
+    }
+}
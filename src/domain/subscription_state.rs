@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use super::arxiv_paper::PaperId;
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionState {
+    pub subscription: String,
+    pub last_updated: NaiveDateTime,
+}
+
+pub trait SubscriptionStateRepositoryTrait {
+    fn find(&self, subscription: &str) -> anyhow::Result<Option<SubscriptionState>>;
+    fn save(&self, state: &SubscriptionState) -> anyhow::Result<usize>;
+    // 購読で保存した論文として記録する。記録済みの論文は無視する
+    fn add_papers(&self, subscription: &str, paper_ids: &[PaperId]) -> anyhow::Result<usize>;
+    fn find_paper_ids(&self, subscription: &str) -> anyhow::Result<Vec<PaperId>>;
+}
+
+impl<T: SubscriptionStateRepositoryTrait + ?Sized> SubscriptionStateRepositoryTrait for &T {
+    fn find(&self, subscription: &str) -> anyhow::Result<Option<SubscriptionState>> {
+        (**self).find(subscription)
+    }
+    fn save(&self, state: &SubscriptionState) -> anyhow::Result<usize> {
+        (**self).save(state)
+    }
+    fn add_papers(&self, subscription: &str, paper_ids: &[PaperId]) -> anyhow::Result<usize> {
+        (**self).add_papers(subscription, paper_ids)
+    }
+    fn find_paper_ids(&self, subscription: &str) -> anyhow::Result<Vec<PaperId>> {
+        (**self).find_paper_ids(subscription)
+    }
+}
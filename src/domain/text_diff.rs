@@ -0,0 +1,89 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diff {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+impl Diff {
+    pub fn text(&self) -> &str {
+        match self {
+            Diff::Same(t) | Diff::Removed(t) | Diff::Added(t) => t,
+        }
+    }
+
+    fn text_mut(&mut self) -> &mut String {
+        match self {
+            Diff::Same(t) | Diff::Removed(t) | Diff::Added(t) => t,
+        }
+    }
+}
+
+// 連続する同じ種類の単語は一つにまとめる
+fn push(diffs: &mut Vec<Diff>, diff: Diff) {
+    if let Some(last) = diffs.last_mut() {
+        if std::mem::discriminant(last) == std::mem::discriminant(&diff) {
+            let text = last.text_mut();
+            text.push(' ');
+            text.push_str(diff.text());
+            return;
+        }
+    }
+    diffs.push(diff);
+}
+
+// 空白で区切った単語単位の差分（最長共通部分列）
+pub fn word_diff(old: &str, new: &str) -> Vec<Diff> {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+    // lcs[i][j] は old[i..] と new[j..] の最長共通部分列の長さ
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diffs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            push(&mut diffs, Diff::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            push(&mut diffs, Diff::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            push(&mut diffs, Diff::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    diffs
+}
+
+// 空白の違いだけなら変更なしとみなす
+pub fn is_changed(old: &str, new: &str) -> bool {
+    !old.split_whitespace().eq(new.split_whitespace())
+}
+
+#[test]
+fn test_word_diff() {
+    assert_eq!(word_diff("Face Parsing in the Wild", "Face  Parsing\nin the Wild"), vec![
+        Diff::Same("Face Parsing in the Wild".to_string()),
+    ]);
+    assert_eq!(word_diff("10 pages, 5 figures", "Accepted to CVPR 2021. 10 pages, 6 figures"), vec![
+        Diff::Added("Accepted to CVPR 2021.".to_string()),
+        Diff::Same("10 pages,".to_string()),
+        Diff::Removed("5".to_string()),
+        Diff::Added("6".to_string()),
+        Diff::Same("figures".to_string()),
+    ]);
+    assert_eq!(word_diff("", "new"), vec![Diff::Added("new".to_string())]);
+    assert!(!is_changed("a  b", "a b"));
+    assert!(is_changed("a b", "a c"));
+}
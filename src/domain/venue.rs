@@ -0,0 +1,214 @@
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Track {
+    Oral,
+    Spotlight,
+    Poster,
+    Workshop,
+}
+
+impl Track {
+    pub fn parse(track: &str) -> Option<Self> {
+        match track {
+            "oral" => Some(Track::Oral),
+            "spotlight" => Some(Track::Spotlight),
+            "poster" => Some(Track::Poster),
+            "workshop" => Some(Track::Workshop),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Track::Oral => "oral",
+            Track::Spotlight => "spotlight",
+            Track::Poster => "poster",
+            Track::Workshop => "workshop",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AcceptanceStatus {
+    Accepted,
+    // 投稿中・査読中
+    UnderReview,
+    // 会議名は書かれているが採否が分からない
+    Unknown,
+}
+
+impl AcceptanceStatus {
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "accepted" => Some(AcceptanceStatus::Accepted),
+            "under_review" => Some(AcceptanceStatus::UnderReview),
+            "unknown" => Some(AcceptanceStatus::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AcceptanceStatus::Accepted => "accepted",
+            AcceptanceStatus::UnderReview => "under_review",
+            AcceptanceStatus::Unknown => "unknown",
+        }
+    }
+}
+
+// コメントやjournal_refから取り出した投稿先
+#[derive(Debug, Clone, PartialEq)]
+pub struct Venue {
+    pub name: String,
+    pub year: Option<i32>,
+    pub track: Option<Track>,
+    pub status: AcceptanceStatus,
+}
+
+impl Venue {
+    // "CVPR 2021 oral" のような表示用の文字列
+    pub fn label(&self) -> String {
+        let mut label = self.name.to_owned();
+        if let Some(year) = self.year {
+            label.push_str(&format!(" {}", year));
+        }
+        if let Some(track) = &self.track {
+            label.push_str(&format!(" {}", track.as_str()));
+        }
+        label
+    }
+}
+
+// 会議・論文誌の正式な表記と、その表記揺れ
+#[derive(Debug, Clone)]
+pub struct VenueAlias {
+    pub name: String,
+    pub aliases: Vec<String>,
+}
+
+thread_local! {
+    // "not yet accepted" や "submitted to" を採択と誤判定しないよう、否定的な表現を先に見る
+    static UNDER_REVIEW: Regex = Regex::new(
+        r"(?i)\b(not\s+(yet\s+)?(been\s+)?accepted|under\s+(review|submission)|in\s+submission|submitted\s+to|rejected)\b"
+    ).unwrap();
+    static ACCEPTED: Regex = Regex::new(
+        r"(?i)\b(accepted|to\s+appear|appear(s|ed)?\s+(in|at)|published\s+(in|at|by)|camera[\s-]ready|proceedings\s+of)\b"
+    ).unwrap();
+}
+
+// 論文ごとに呼ばれるので、正規表現はスレッドごとに一度だけコンパイルする
+pub fn acceptance_status(text: &str) -> AcceptanceStatus {
+    if UNDER_REVIEW.with(|re| re.is_match(text)) {
+        AcceptanceStatus::UnderReview
+    } else if ACCEPTED.with(|re| re.is_match(text)) {
+        AcceptanceStatus::Accepted
+    } else {
+        AcceptanceStatus::Unknown
+    }
+}
+
+pub struct VenueExtractor {
+    // (正式な表記, 表記揺れにマッチする正規表現)
+    venues: Vec<(String, Regex)>,
+    tracks: Vec<(Regex, Track)>,
+    // 会議名と離れて書かれている年
+    year: Regex,
+}
+
+impl VenueExtractor {
+    pub fn new(venues: &[VenueAlias]) -> anyhow::Result<Self> {
+        let venues = venues.iter().map(|v| {
+            let names: Vec<String> = std::iter::once(&v.name).chain(v.aliases.iter())
+                .map(|a| regex::escape(a).replace(r"\ ", r"\s+"))
+                .collect();
+            // "CVPR 2021", "CVPR'21", "CVPR2021" の年も一緒に取り出す
+            let re = Regex::new(&format!(r"(?i)\b(?:{})(?:\s*'?(\d{{4}}|\d{{2}}))?\b", names.join("|")))?;
+            Ok((v.name.to_owned(), re))
+        }).collect::<anyhow::Result<Vec<(String, Regex)>>>()?;
+        let tracks = vec![
+            (Regex::new(r"(?i)\bworkshops?\b")?, Track::Workshop),
+            (Regex::new(r"(?i)\boral\b")?, Track::Oral),
+            (Regex::new(r"(?i)\bspotlight\b")?, Track::Spotlight),
+            (Regex::new(r"(?i)\bposter\b")?, Track::Poster),
+        ];
+        let year = Regex::new(r"\b((?:19|20)\d{2})\b")?;
+        Ok(Self { venues, tracks, year })
+    }
+
+    // 最初に出てくる会議を投稿先とする。journal_refがあれば掲載済みとしてそちらを優先する
+    pub fn extract(&self, comment: &str, journal_ref: Option<&str>) -> Option<Venue> {
+        if let Some(journal_ref) = journal_ref {
+            if let Some(mut venue) = self.find(journal_ref) {
+                venue.status = AcceptanceStatus::Accepted;
+                return Some(venue);
+            }
+        }
+        let mut venue = self.find(comment)?;
+        venue.status = acceptance_status(comment);
+        Some(venue)
+    }
+
+    fn find(&self, text: &str) -> Option<Venue> {
+        let (name, caps) = self.venues.iter()
+            .filter_map(|(name, re)| re.captures(text).map(|caps| (name, caps)))
+            .min_by_key(|(_, caps)| caps.get(0).unwrap().start())?;
+        let year = match caps.get(1) {
+            Some(y) => y.as_str().parse::<i32>().ok().map(|y| if y < 100 { 2000 + y } else { y }),
+            None => self.year.captures(text)
+                .and_then(|caps| caps[1].parse().ok()),
+        };
+        Some(Venue {
+            name: name.to_owned(),
+            year,
+            track: self.track(text),
+            status: AcceptanceStatus::Unknown,
+        })
+    }
+
+    fn track(&self, text: &str) -> Option<Track> {
+        self.tracks.iter()
+            .find(|(re, _)| re.is_match(text))
+            .map(|(_, track)| track.clone())
+    }
+}
+
+#[test]
+fn test_venue_extractor() {
+    let extractor = VenueExtractor::new(&[
+        VenueAlias { name: "CVPR".to_string(), aliases: vec!["Computer Vision and Pattern Recognition".to_string()] },
+        VenueAlias { name: "NeurIPS".to_string(), aliases: vec!["NIPS".to_string()] },
+        VenueAlias { name: "TPAMI".to_string(), aliases: vec!["IEEE Transactions on Pattern Analysis and Machine Intelligence".to_string()] },
+    ]).unwrap();
+
+    let venue = extractor.extract("Accepted to CVPR 2021 as an oral presentation. 10 pages", None).unwrap();
+    assert_eq!(venue, Venue {
+        name: "CVPR".to_string(),
+        year: Some(2021),
+        track: Some(Track::Oral),
+        status: AcceptanceStatus::Accepted,
+    });
+    assert_eq!(venue.label(), "CVPR 2021 oral");
+
+    let venue = extractor.extract("Submitted to NIPS'20, not yet accepted", None).unwrap();
+    assert_eq!(venue.name, "NeurIPS");
+    assert_eq!(venue.year, Some(2020));
+    assert_eq!(venue.status, AcceptanceStatus::UnderReview);
+
+    let venue = extractor.extract("CVPR2022 Workshop on Face Analysis", None).unwrap();
+    assert_eq!(venue.year, Some(2022));
+    assert_eq!(venue.track, Some(Track::Workshop));
+    assert_eq!(venue.status, AcceptanceStatus::Unknown);
+
+    let venue = extractor.extract(
+        "Extended version of our CVPR paper",
+        Some("IEEE Transactions on Pattern Analysis and Machine Intelligence, 2021"),
+    ).unwrap();
+    assert_eq!(venue.name, "TPAMI");
+    assert_eq!(venue.year, Some(2021));
+    assert_eq!(venue.status, AcceptanceStatus::Accepted);
+
+    assert!(extractor.extract("10 pages, 5 figures", None).is_none());
+    assert_eq!(acceptance_status("This paper has not been accepted"), AcceptanceStatus::UnderReview);
+    assert_eq!(acceptance_status("To appear in ICML"), AcceptanceStatus::Accepted);
+}
@@ -1,8 +1,18 @@
 extern crate reqwest;
 extern crate diesel;
 
+#[macro_use]
+pub mod database;
 pub mod arxiv_paper;
 pub mod arxiv_api;
-pub mod sqlite;
+pub mod arxiv_oai;
+pub mod arxiv_rss;
+pub mod memory;
+pub mod oai_harvest;
+pub mod pdf_library;
+pub mod recording;
+pub mod repositories;
+pub mod retry;
 pub mod slack_api;
-pub mod slack_notifications;
\ No newline at end of file
+pub mod slack_notifications;
+pub mod subscription_state;
\ No newline at end of file
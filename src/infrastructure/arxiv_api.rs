@@ -1,15 +1,31 @@
+pub mod query;
+
+use self::query::{Field, Query};
+use super::recording;
+use super::retry::{get_text, RetryError, RetryPolicy};
 use crate::domain::arxiv_api as I;
+use crate::domain::author_name::{find_followed, AuthorName};
+use crate::domain::venue::{acceptance_status, AcceptanceStatus};
 use chrono::NaiveDateTime;
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use quick_xml;
 use quick_xml::de::from_str;
 use regex::Regex;
-use reqwest;
 use serde::Deserialize;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::{thread, time};
+
+const DEFAULT_API_URL: &str = "https://export.arxiv.org/api/query";
+// arXiv APIの利用規約で連続リクエストの間は3秒空けることになっている
+const API_REQUEST_INTERVAL_MILLIS: u64 = 3000;
+const DEFAULT_MAX_TOTAL_RESULTS: u32 = 1000;
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct Author {
     name: String,
+    #[serde(default)]
+    affiliation: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
@@ -23,6 +39,11 @@ pub struct PrimaryCategory {
     term: String,
 }
 
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Category {
+    term: String,
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct Arxiv {
     primary_category: PrimaryCategory,
@@ -37,15 +58,56 @@ pub struct Entry {
     summary: String,
     author: Vec<Author>,
     comment: Option<String>,
+    journal_ref: Option<String>,
+    doi: Option<String>,
     link: Vec<Link>,
     primary_category: PrimaryCategory,
+    #[serde(default)]
+    category: Vec<Category>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct Feed {
+    #[serde(rename = "totalResults")]
+    total_results: u32,
+    #[serde(rename = "startIndex")]
+    start_index: u32,
+    #[serde(rename = "itemsPerPage")]
+    items_per_page: u32,
+    #[serde(default)]
     entry: Vec<Entry>,
 }
 
+pub(crate) fn is_accepted(comment: &str) -> bool {
+    acceptance_status(comment) == AcceptanceStatus::Accepted
+}
+
+// categoryにはACMやMSCの分類(I.4.8, 68T45)も含まれるので、arXivのカテゴリ(cs.CV, hep-th)だけを取り出す
+pub(crate) fn is_arxiv_category(term: &str) -> bool {
+    term.starts_with(|c: char| c.is_ascii_lowercase())
+}
+
+// "I.4.8; I.2.10" のように一つにまとめて書かれている分類を分割する
+pub(crate) fn split_classes(classes: &str) -> Vec<String> {
+    classes.split(&[';', ','][..])
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+// split_authorsで使う区切り。論文ごとにコンパイルしないように呼び出し側で一度だけ作る
+pub(crate) fn author_separator() -> Regex {
+    Regex::new(r"\s*,\s*(?:and\s+)?|\s+and\s+").unwrap()
+}
+
+// "A, B and C" のような一つの文字列で書かれた著者を分割する
+pub(crate) fn split_authors(authors: &str, separator: &Regex) -> Vec<String> {
+    separator.split(authors.trim())
+        .filter(|a| !a.is_empty())
+        .map(|a| a.to_string())
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub enum SortBy {
     Relevance,
@@ -61,16 +123,29 @@ pub enum SortOrder {
 
 #[derive(Debug, Clone)]
 pub struct ArxivAPI {
+    api_url: String,
+    id_list: Option<Vec<String>>,
     search_categories: Vec<String>,
     search_title_words: Option<Vec<String>>,
     exclude_title_words: Option<Vec<String>>,
     search_abstract_words: Option<Vec<String>>,
     exclude_abstract_words: Option<Vec<String>>,
+    query: Option<Query>,
+    submitted_date: Option<(NaiveDateTime, NaiveDateTime)>,
+    follow_authors: Option<Vec<String>>,
     sort_by: SortBy,
     sort_order: SortOrder,
     max_result: u32,
     start: Option<u32>,
+    max_total_results: u32,
+    since: Option<NaiveDateTime>,
+    retry_policy: RetryPolicy,
+    request_interval: time::Duration,
+    record_dir: Option<PathBuf>,
+    replay_dir: Option<PathBuf>,
     filter_by_main_category: bool,
+    // 直前のqueryがmax_total_resultsで打ち切られ、取得していない論文が残っているか
+    truncated: Cell<bool>,
 }
 
 impl ArxivAPI {
@@ -87,76 +162,184 @@ impl ArxivAPI {
         filter_by_main_category: bool,
     ) -> Self {
         Self {
+            api_url: DEFAULT_API_URL.to_string(),
+            id_list: None,
             search_categories: search_categories.clone(),
             search_title_words: search_title_words.clone(),
             exclude_title_words: exclude_title_words.clone(),
             search_abstract_words: search_abstract_words.clone(),
             exclude_abstract_words: exclude_abstract_words.clone(),
+            query: None,
+            submitted_date: None,
+            follow_authors: None,
             sort_by,
             sort_order,
             max_result,
             start,
+            max_total_results: DEFAULT_MAX_TOTAL_RESULTS,
+            since: None,
+            retry_policy: RetryPolicy::default(),
+            request_interval: time::Duration::from_millis(API_REQUEST_INTERVAL_MILLIS),
+            record_dir: None,
+            replay_dir: None,
             filter_by_main_category,
+            truncated: Cell::new(false),
         }
     }
 
-    fn generate_search_queries(&self) -> String {
-        let q = self
-            .search_categories
-            .iter()
-            .map(|s| format!("cat:{}", s))
-            .collect::<Vec<String>>()
-            .join("+OR+");
-        let q = format!("({})", &q);
-        let q = match &self.search_title_words {
-            Some(ws) => format!(
-                "{}+AND+({})",
-                &q,
-                &ws.iter()
-                    .map(|s| format!("ti:\"{}\"", s))
-                    .collect::<Vec<String>>()
-                    .join("+OR+"),
-            ),
-            None => q,
-        };
-        let q = match &self.exclude_title_words {
-            Some(ws) => format!(
-                "{}+ANDNOT+({})",
-                &q,
-                &ws.iter()
-                    .map(|s| format!("ti:\"{}\"", s))
-                    .collect::<Vec<String>>()
-                    .join("+OR+"),
-            ),
-            None => q,
-        };
-        let q = match &self.search_abstract_words {
-            Some(ws) => format!(
-                "{}+AND+({})",
-                &q,
-                &ws.iter()
-                    .map(|s| format!("abs:\"{}\"", s))
-                    .collect::<Vec<String>>()
-                    .join("+OR+"),
-            ),
-            None => q,
-        };
-        let q = match &self.exclude_abstract_words {
-            Some(ws) => format!(
-                "{}+ANDNOT+({})",
-                &q,
-                &ws.iter()
-                    .map(|s| format!("abs:\"{}\"", s))
-                    .collect::<Vec<String>>()
-                    .join("+OR+"),
-            ),
-            None => q,
+    // 検索ではなくarXiv IDを指定して論文を取得する
+    pub fn with_id_list(id_list: &[String]) -> Self {
+        let mut api = Self::new(
+            &vec![],
+            &None,
+            &None,
+            &None,
+            &None,
+            SortBy::Relevance,
+            SortOrder::Descending,
+            id_list.len() as u32,
+            None,
+            false,
+        );
+        api.id_list = Some(id_list.to_vec());
+        api
+    }
+
+    pub fn api_url(&mut self, api_url: &str) -> &mut Self {
+        self.api_url = api_url.to_string();
+        self
+    }
+
+    pub fn request_interval(&mut self, request_interval: time::Duration) -> &mut Self {
+        self.request_interval = request_interval;
+        self
+    }
+
+    pub fn record(&mut self, dir: &Path) -> &mut Self {
+        self.record_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    // ネットワークにはアクセスせず、recordで保存したレスポンスを使う
+    pub fn replay(&mut self, dir: &Path) -> &mut Self {
+        self.replay_dir = Some(dir.to_path_buf());
+        self.request_interval = time::Duration::from_millis(0);
+        self
+    }
+
+    pub fn max_total_results(&mut self, max_total_results: u32) -> &mut Self {
+        self.max_total_results = max_total_results;
+        self
+    }
+
+    pub fn since(&mut self, since: Option<NaiveDateTime>) -> &mut Self {
+        self.since = since;
+        self
+    }
+
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn expression(&mut self, query: Query) -> &mut Self {
+        self.query = Some(query);
+        self
+    }
+
+    pub fn submitted_between(&mut self, from: NaiveDateTime, to: NaiveDateTime) -> &mut Self {
+        self.submitted_date = Some((from, to));
+        self
+    }
+
+    pub fn follow_authors(&mut self, authors: &[String]) -> &mut Self {
+        self.follow_authors = Some(authors.to_vec());
+        self
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.get()
+    }
+
+    pub fn search_query(&self) -> anyhow::Result<String> {
+        self.generate_search_queries()
+    }
+
+    // 設定のリスト形式の検索条件とqueryをまとめて一つの検索式にする
+    fn search_expression(&self) -> Query {
+        let any_of = |field: Field, words: &Vec<String>| {
+            Query::Or(words.iter().map(|w| Query::phrase(field.clone(), w)).collect())
         };
-        q
+        let mut children = vec![];
+        if !self.search_categories.is_empty() {
+            children.push(Query::Or(
+                self.search_categories.iter().map(|c| Query::word(Field::Category, c)).collect()));
+        }
+        if let Some(ws) = &self.search_title_words {
+            children.push(any_of(Field::Title, ws));
+        }
+        if let Some(ws) = &self.exclude_title_words {
+            children.push(Query::Not(Box::new(any_of(Field::Title, ws))));
+        }
+        if let Some(ws) = &self.search_abstract_words {
+            children.push(any_of(Field::Abstract, ws));
+        }
+        if let Some(ws) = &self.exclude_abstract_words {
+            children.push(Query::Not(Box::new(any_of(Field::Abstract, ws))));
+        }
+        match &self.query {
+            Some(Query::And(qs)) => children.extend(qs.iter().cloned()),
+            Some(q) => children.push(q.clone()),
+            None => {}
+        }
+        // フォローしている著者の論文はカテゴリなどの条件によらず取得する
+        if let Some(authors) = self.author_expression() {
+            children = match children.is_empty() {
+                true => vec![authors],
+                false if children.len() == 1 => vec![Query::Or(vec![children.remove(0), authors])],
+                false => vec![Query::Or(vec![Query::And(children), authors])],
+            };
+        }
+        if let Some((from, to)) = self.submitted_date {
+            children.push(Query::SubmittedDate { from, to });
+        }
+        Query::And(children)
     }
 
-    fn generate_api_url(&self) -> String {
-        let search_query = format!("&search_query={}", self.generate_search_queries());
+    fn author_expression(&self) -> Option<Query> {
+        let authors = self.follow_authors.as_ref()?;
+        // arXivの著者検索は表記揺れに弱いので姓だけで検索し、取得後に名前を照合する
+        let mut last_names: Vec<String> = authors.iter()
+            .map(|a| AuthorName::parse(a).last.replace(" ", "_"))
+            .filter(|last| !last.is_empty())
+            .collect();
+        last_names.sort();
+        last_names.dedup();
+        let mut terms: Vec<Query> = last_names.iter().map(|last| Query::word(Field::Author, last)).collect();
+        match terms.len() {
+            1 => terms.pop(),
+            _ => Some(Query::Or(terms)),
+        }
+    }
+
+    fn has_conditions(&self) -> bool {
+        !self.search_categories.is_empty()
+            || self.search_title_words.is_some()
+            || self.exclude_title_words.is_some()
+            || self.search_abstract_words.is_some()
+            || self.exclude_abstract_words.is_some()
+            || self.query.is_some()
+    }
+
+    fn generate_search_queries(&self) -> anyhow::Result<String> {
+        self.search_expression().compile()
+    }
+
+    fn generate_api_url(&self, start: u32, max_result: u32) -> anyhow::Result<String> {
+        let search_query = match &self.id_list {
+            Some(ids) => format!("&id_list={}", ids.join(",")),
+            None => format!("&search_query={}", self.generate_search_queries()?),
+        };
         let sort_by = format!(
             "&sortBy={}",
             match self.sort_by {
@@ -172,20 +355,15 @@ impl ArxivAPI {
                 SortOrder::Descending => "descending",
             }
         );
-        let max_result = format!("&max_results={}", self.max_result);
-        let start = format!(
-            "&start={}",
-            match self.start {
-                Some(n) => n,
-                None => 0,
-            }
-        );
-        let url = "https://export.arxiv.org/api/query";
+        let max_result = format!("&max_results={}", max_result);
+        let start = format!("&start={}", start);
         let params = &format!(
             "?{}{}{}{}{}",
             search_query, sort_by, sort_order, max_result, start
         );
+        // search_queryの語句の中の%はquery.rsでエンコード済み
         const FRAGMENT: &AsciiSet = &NON_ALPHANUMERIC
+            .remove(b'%')
             .remove(b'?')
             .remove(b'&')
             .remove(b'=')
@@ -195,22 +373,42 @@ impl ArxivAPI {
             .remove(b'-')
             .remove(b'_');
         let params = utf8_percent_encode(params, FRAGMENT).to_string();
-        return url.to_owned() + &params;
+        Ok(self.api_url.to_owned() + &params)
     }
 
-    fn fetch_xml(&self) -> anyhow::Result<String> {
-        let api_url = self.generate_api_url();
+    fn fetch_xml(&self, start: u32, max_result: u32) -> Result<String, RetryError> {
+        let api_url = self.generate_api_url(start, max_result).map_err(RetryError::Fatal)?;
         println!("{}", api_url);
-        let body = reqwest::blocking::get(&api_url)?.text()?;
-        Ok(body)
+        if let Some(dir) = &self.replay_dir {
+            return recording::replay(dir, &api_url).map_err(RetryError::Fatal);
+        }
+        let xml = get_text(&api_url)?;
+        if let Some(dir) = &self.record_dir {
+            recording::record(dir, &api_url, &xml).map_err(RetryError::Fatal)?;
+        }
+        Ok(xml)
+    }
+
+    fn fetch_feed(&self, start: u32, max_result: u32) -> anyhow::Result<Feed> {
+        self.retry_policy.run(|| {
+            let xml = self.fetch_xml(start, max_result)?;
+            let feed = self.to_feed(xml).map_err(RetryError::Fatal)?;
+            // 負荷が高いときarXivはtotalResultsが残っているのに空のフィードを返すことがある
+            if feed.entry.is_empty() && max_result > 0 && start < feed.total_results {
+                return Err(RetryError::transient(anyhow::anyhow!(
+                    "arXiv returned an empty feed at start={} although totalResults={}",
+                    start, feed.total_results
+                )));
+            }
+            Ok(feed)
+        })
     }
 
     fn convert(&self, feed: &Feed) -> Vec<I::Paper> {
         let mut papers: Vec<I::Paper> = Vec::new();
         for entry in &feed.entry {
             let comment = entry.comment.to_owned().unwrap_or("".to_string());
-            let l_comment = comment.to_lowercase();
-            let is_accepted = l_comment.contains("accept") || l_comment.contains("appear");
+            let is_accepted = is_accepted(&comment);
             papers.push(I::Paper {
                 title: entry.title.replace("\n", " "),
                 updated: NaiveDateTime::parse_from_str(&entry.updated, "%Y-%m-%dT%H:%M:%SZ")
@@ -236,6 +434,22 @@ impl ArxivAPI {
                     .map(|x| x.name.replace("\n", " "))
                     .collect(),
                 category: entry.primary_category.term.replace("\n", ""),
+                categories: entry.category.iter()
+                    .map(|c| c.term.replace("\n", ""))
+                    .filter(|c| is_arxiv_category(c))
+                    .collect(),
+                classes: entry.category.iter()
+                    .filter(|c| !is_arxiv_category(&c.term))
+                    .flat_map(|c| split_classes(&c.term))
+                    .collect(),
+                journal_ref: entry.journal_ref.as_ref().map(|j| j.replace("\n", " ")),
+                doi: entry.doi.as_ref().map(|d| d.trim().to_string()),
+                affiliations: entry.author.iter()
+                    .flat_map(|a| a.affiliation.iter().map(move |af| I::Affiliation {
+                        author: a.name.replace("\n", " "),
+                        name: af.replace("\n", " "),
+                    }))
+                    .collect(),
                 summary: entry.summary.replace("\n", " "),
                 comment: comment.replace("\n", " "),
                 is_accepted,
@@ -244,25 +458,332 @@ impl ArxivAPI {
         papers
     }
 
-    fn to_papers(&self, xml: String) -> anyhow::Result<Vec<I::Paper>> {
+    fn to_feed(&self, xml: String) -> anyhow::Result<Feed> {
+        // DOIは<arxiv:doi>から取得する。doiのlinkは他のlinkと離れた位置にあり、
+        // quick-xmlが同じフィールドの重複として扱ってしまうので取り除いておく
         let re = Regex::new("<link title=\"doi\".*>\n")?;
         let xml = re.replace_all(&xml, "");
         let feed: Feed = from_str(&xml)?;
-        Ok(self.convert(&feed))
+        Ok(feed)
+    }
+
+    fn is_newer_than_since(&self, paper: &I::Paper) -> bool {
+        match self.since {
+            Some(since) => paper.updated > since,
+            None => true,
+        }
+    }
+
+    // 取得した論文と、max_total_resultsで打ち切ったかどうかを返す
+    fn fetch_all(&self) -> anyhow::Result<(Vec<I::Paper>, bool)> {
+        let mut papers: Vec<I::Paper> = Vec::new();
+        let mut start = self.start.unwrap_or(0);
+        let mut n_fetched: u32 = 0;
+        loop {
+            let max_result = self.max_result.min(self.max_total_results - n_fetched);
+            let feed = self.fetch_feed(start, max_result)?;
+            let n_entries = feed.entry.len() as u32;
+            let (new_papers, old_papers): (Vec<I::Paper>, Vec<I::Paper>) = self
+                .convert(&feed)
+                .into_iter()
+                .partition(|p| self.is_newer_than_since(p));
+            papers.extend(new_papers);
+            // 更新日時の降順で取得している場合、前回までに取得済みの論文が出てきたら以降のページも全て取得済み
+            let reached_since = !old_papers.is_empty()
+                && matches!(self.sort_by, SortBy::LastUpdatedDate)
+                && matches!(self.sort_order, SortOrder::Descending);
+            n_fetched += n_entries;
+            start = feed.start_index + n_entries;
+            if n_entries == 0
+                || reached_since
+                || start >= feed.total_results
+            {
+                return Ok((papers, false));
+            }
+            if n_fetched >= self.max_total_results {
+                return Ok((papers, true));
+            }
+            thread::sleep(self.request_interval);
+        }
     }
 }
 
 impl I::ArxivAPITrait for ArxivAPI {
     fn query(&self) -> anyhow::Result<Vec<I::Paper>> {
-        let xml = self.fetch_xml()?;
-        let mut papers = self.to_papers(xml)?;
-        if self.filter_by_main_category {
-            papers = papers
-                .iter()
-                .filter(|&p| self.search_categories.contains(&p.category))
-                .cloned()
-                .collect::<Vec<I::Paper>>();
+        let authors = match &self.follow_authors {
+            Some(authors) => authors,
+            None => {
+                let (mut papers, truncated) = self.fetch_all()?;
+                self.truncated.set(truncated);
+                if self.filter_by_main_category {
+                    papers = papers
+                        .iter()
+                        .filter(|&p| self.search_categories.contains(&p.category))
+                        .cloned()
+                        .collect::<Vec<I::Paper>>();
+                }
+                return Ok(papers);
+            }
+        };
+        // 著者は姓だけで検索するので、取得後に名前を照合できるように他の条件とは別に検索する
+        let mut by_authors = self.clone();
+        by_authors.search_categories = vec![];
+        by_authors.search_title_words = None;
+        by_authors.exclude_title_words = None;
+        by_authors.search_abstract_words = None;
+        by_authors.exclude_abstract_words = None;
+        by_authors.query = None;
+        by_authors.filter_by_main_category = false;
+        let (mut papers, mut truncated) = by_authors.fetch_all()?;
+        papers.retain(|p| !find_followed(&p.authors, authors).is_empty());
+        if self.has_conditions() {
+            let mut rest = self.clone();
+            rest.follow_authors = None;
+            let rest_papers = rest.query()?;
+            truncated |= rest.is_truncated();
+            papers.retain(|p| rest_papers.iter().all(|r| r.url != p.url));
+            papers.extend(rest_papers);
         }
+        self.truncated.set(truncated);
         Ok(papers)
     }
 }
+
+#[cfg(test)]
+fn mock_arxiv_api(url: &str, max_result: u32) -> ArxivAPI {
+    let mut api = ArxivAPI::new(
+        &vec!["cs.CV".to_string()],
+        &None,
+        &None,
+        &None,
+        &None,
+        SortBy::LastUpdatedDate,
+        SortOrder::Descending,
+        max_result,
+        None,
+        false,
+    );
+    api.api_url(url)
+        .request_interval(time::Duration::from_millis(0))
+        .retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: time::Duration::from_millis(0),
+            max_delay: time::Duration::from_millis(0),
+            jitter: time::Duration::from_millis(0),
+        });
+    api
+}
+
+#[cfg(test)]
+const PAGE1: &str = include_str!("../../tests/fixtures/arxiv/page1.xml");
+#[cfg(test)]
+const PAGE2: &str = include_str!("../../tests/fixtures/arxiv/page2.xml");
+#[cfg(test)]
+const EMPTY: &str = include_str!("../../tests/fixtures/arxiv/empty.xml");
+
+#[test]
+fn test_query_pagination() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let papers = mock_arxiv_api(&server.url(), 2).query().unwrap();
+    assert_eq!(papers.len(), 3);
+    assert_eq!(papers[0].url, "http://arxiv.org/abs/2104.01234v2");
+    assert_eq!(papers[0].authors, vec!["Taro Yamada", "Jane Doe"]);
+    assert_eq!(papers[0].categories, vec!["cs.CV", "cs.LG"]);
+    assert_eq!(papers[0].classes, vec!["I.4.8"]);
+    assert_eq!(papers[0].doi.as_deref(), Some("10.1000/xyz123"));
+    assert_eq!(papers[0].journal_ref.as_deref(), Some("CVPR 2021"));
+    assert_eq!(papers[0].affiliations, vec![I::Affiliation {
+        author: "Taro Yamada".to_string(),
+        name: "Univ. of Tokyo".to_string(),
+    }]);
+    assert_eq!(papers[1].doi, None);
+    assert_eq!(papers[2].title, "Third Paper");
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].contains("&max_results=2&start=0"));
+    assert!(requests[1].contains("&max_results=2&start=2"));
+
+    // max_total_resultsに達したらページングをやめる
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let papers = mock_arxiv_api(&server.url(), 2).max_total_results(2).query().unwrap();
+    assert_eq!(papers.len(), 2);
+    assert_eq!(server.requests().len(), 1);
+
+    // 前回取得済みの論文が出てきたらページングをやめる
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let since = NaiveDateTime::parse_from_str("2021-04-10T00:00:00Z", "%Y-%m-%dT%H:%M:%SZ").unwrap();
+    let papers = mock_arxiv_api(&server.url(), 2).since(Some(since)).query().unwrap();
+    assert_eq!(papers.len(), 1);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn test_query_errors() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    // 一時的なエラーや空のフィードは再試行する
+    let server = MockServer::start(vec![
+        MockResponse::new(200, PAGE1),
+        MockResponse::new(503, ""),
+        MockResponse::new(200, EMPTY),
+        MockResponse::new(200, PAGE2),
+    ]);
+    let papers = mock_arxiv_api(&server.url(), 2).query().unwrap();
+    assert_eq!(papers.len(), 3);
+    assert_eq!(server.requests().len(), 4);
+
+    let server = MockServer::start(vec![MockResponse::new(200, EMPTY)]);
+    assert!(mock_arxiv_api(&server.url(), 2).query().is_err());
+    assert_eq!(server.requests().len(), 3);
+
+    let server = MockServer::start(vec![MockResponse::new(400, "")]);
+    assert!(mock_arxiv_api(&server.url(), 2).query().is_err());
+    assert_eq!(server.requests().len(), 1);
+
+    let server = MockServer::start(vec![MockResponse::new(200, "<html>not a feed</html>")]);
+    assert!(mock_arxiv_api(&server.url(), 2).query().is_err());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn test_record_and_replay() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let dir = std::env::temp_dir().join(format!("arxiv-bot-recording-{}", std::process::id()));
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let recorded = mock_arxiv_api(&server.url(), 2).record(&dir).query().unwrap();
+    let url = server.url();
+    drop(server);
+
+    let replayed = mock_arxiv_api(&url, 2).replay(&dir).query().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(replayed.len(), recorded.len());
+    assert_eq!(replayed[2].url, recorded[2].url);
+}
+
+#[test]
+fn test_replay_lookback_days() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let dir = std::env::temp_dir().join(format!("arxiv-bot-recording-lookback-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    // lookback_daysでは範囲の終わりが実行時刻になるので、記録したときと再生するときでURLが変わる
+    let recorded_at = NaiveDateTime::parse_from_str("2026-10-15T12:00:00Z", "%Y-%m-%dT%H:%M:%SZ").unwrap();
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let recorded = mock_arxiv_api(&server.url(), 2)
+        .submitted_between(recorded_at - chrono::Duration::days(2), recorded_at)
+        .record(&dir)
+        .query()
+        .unwrap();
+    let url = server.url();
+    drop(server);
+
+    let replayed_at = recorded_at + chrono::Duration::minutes(30);
+    let replayed = mock_arxiv_api(&url, 2)
+        .submitted_between(replayed_at - chrono::Duration::days(2), replayed_at)
+        .replay(&dir)
+        .query()
+        .unwrap();
+    // 範囲以外の条件が違う場合は再生しない
+    assert!(mock_arxiv_api(&url, 3).replay(&dir).query().is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(replayed.len(), recorded.len());
+}
+
+#[test]
+fn test_query_id_list() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE2)]);
+    let mut api = ArxivAPI::with_id_list(&["2103.09999".to_string()]);
+    let papers = api.api_url(&server.url()).query().unwrap();
+    assert_eq!(papers.len(), 1);
+    assert!(server.requests()[0].contains("id_list=2103.09999&"));
+    assert!(!server.requests()[0].contains("search_query"));
+}
+
+#[test]
+fn test_generate_search_queries() {
+    let mut api = ArxivAPI::new(
+        &vec!["cs.CV".to_string(), "eess.IV".to_string()],
+        &Some(vec!["Face".to_string(), "face".to_string()]),
+        &Some(vec!["Surface".to_string()]),
+        &Some(vec!["face".to_string()]),
+        &None,
+        SortBy::LastUpdatedDate,
+        SortOrder::Descending,
+        100,
+        None,
+        false,
+    );
+    assert_eq!(
+        api.generate_search_queries().unwrap(),
+        r#"(cat:cs.CV+OR+cat:eess.IV)+AND+(ti:"Face"+OR+ti:"face")+ANDNOT+(ti:"Surface")+AND+(abs:"face")"#
+    );
+    api.expression(query::parse("au:Smith AND NOT co:workshop").unwrap());
+    assert_eq!(
+        api.generate_search_queries().unwrap(),
+        r#"(cat:cs.CV+OR+cat:eess.IV)+AND+(ti:"Face"+OR+ti:"face")+ANDNOT+(ti:"Surface")+AND+(abs:"face")+AND+au:Smith+ANDNOT+co:workshop"#
+    );
+    api.submitted_between(
+        NaiveDateTime::parse_from_str("2026-10-01T00:00:00Z", "%Y-%m-%dT%H:%M:%SZ").unwrap(),
+        NaiveDateTime::parse_from_str("2026-10-15T23:59:00Z", "%Y-%m-%dT%H:%M:%SZ").unwrap(),
+    );
+    assert!(api.generate_search_queries().unwrap()
+        .ends_with("+AND+submittedDate:[202610010000+TO+202610152359]"));
+
+    // 語句の中の&や+がURLのパラメータの区切りや空白にならない
+    let mut api = ArxivAPI::new(&vec![], &Some(vec!["R&D".to_string()]), &None, &Some(vec!["C++ 100%".to_string()]), &None,
+                                SortBy::LastUpdatedDate, SortOrder::Descending, 100, None, false);
+    assert_eq!(
+        api.api_url("http://localhost/api").generate_api_url(0, 10).unwrap(),
+        "http://localhost/api?&search_query=%28ti:%22R%26D%22%29+AND+%28abs:%22C%2B%2B%20100%25%22%29\
+         &sortBy=lastUpdatedDate&sortOrder=descending&max_results=10&start=0"
+    );
+}
+
+#[test]
+fn test_query_follow_authors() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(200, PAGE1),
+        MockResponse::new(200, PAGE2),
+        MockResponse::new(200, PAGE2),
+    ]);
+    let mut api = mock_arxiv_api(&server.url(), 2);
+    api.follow_authors(&["Doe, J.".to_string()]);
+    assert_eq!(api.generate_search_queries().unwrap(), "((cat:cs.CV)+OR+au:doe)");
+    // 著者の論文はカテゴリによらず取得し、姓だけ一致する著者の論文は除く
+    let papers = api.query().unwrap();
+    let urls: Vec<&str> = papers.iter().map(|p| p.url.as_str()).collect();
+    assert_eq!(urls, vec!["http://arxiv.org/abs/2104.01234v2", "http://arxiv.org/abs/2103.09999v1"]);
+    let requests = server.requests();
+    assert!(requests[0].contains("search_query=au:doe&") && requests[1].contains("search_query=au:doe&"));
+    assert!(requests[2].contains("search_query=%28cat:cs.CV%29&"));
+}
+
+#[test]
+fn test_query_truncated() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let mut api = mock_arxiv_api(&server.url(), 2);
+    api.max_total_results(2);
+    // 3件のうち2件で打ち切ったので、取得していない論文が残っている
+    assert_eq!(api.query().unwrap().len(), 2);
+    assert!(api.is_truncated());
+
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let api = mock_arxiv_api(&server.url(), 2);
+    assert_eq!(api.query().unwrap().len(), 3);
+    assert!(!api.is_truncated());
+    // 前回の位置に達して止まった場合は打ち切りではない
+    let server = MockServer::start(vec![MockResponse::new(200, PAGE1), MockResponse::new(200, PAGE2)]);
+    let mut api = mock_arxiv_api(&server.url(), 2);
+    api.max_total_results(2).since(Some(chrono::NaiveDate::from_ymd(2100, 1, 1).and_hms(0, 0, 0)));
+    assert!(api.query().unwrap().is_empty());
+    assert!(!api.is_truncated());
+}
@@ -0,0 +1,285 @@
+use anyhow::{anyhow, bail};
+use chrono::NaiveDateTime;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+// 語句の中でURLのクエリパラメータの区切りや空白の代わりとして解釈される文字。
+// 残りの文字はURLを組み立てるときにまとめてエンコードされる
+const TERM: &AsciiSet = &CONTROLS.add(b'&').add(b'=').add(b'+').add(b'#').add(b'%');
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    All,
+    Title,
+    Abstract,
+    Author,
+    Comment,
+    Category,
+    JournalRef,
+}
+
+impl Field {
+    fn parse(prefix: &str) -> anyhow::Result<Self> {
+        match prefix {
+            "all" => Ok(Field::All),
+            "ti" => Ok(Field::Title),
+            "abs" => Ok(Field::Abstract),
+            "au" => Ok(Field::Author),
+            "co" => Ok(Field::Comment),
+            "cat" => Ok(Field::Category),
+            "jr" => Ok(Field::JournalRef),
+            _ => bail!("Unknown field prefix `{}:` (use one of all, ti, abs, au, co, cat, jr)", prefix),
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            Field::All => "all",
+            Field::Title => "ti",
+            Field::Abstract => "abs",
+            Field::Author => "au",
+            Field::Comment => "co",
+            Field::Category => "cat",
+            Field::JournalRef => "jr",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term { field: Field, text: String, phrase: bool },
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    SubmittedDate { from: NaiveDateTime, to: NaiveDateTime },
+}
+
+impl Query {
+    pub fn phrase(field: Field, text: &str) -> Self {
+        Query::Term { field, text: text.to_string(), phrase: true }
+    }
+
+    pub fn word(field: Field, text: &str) -> Self {
+        Query::Term { field, text: text.to_string(), phrase: false }
+    }
+
+    // arXivのsearch_queryの形式に変換する
+    // arXivには単独のNOTがないため、NOTはANDの右辺(ANDNOT)にしか書けない
+    pub fn compile(&self) -> anyhow::Result<String> {
+        match self {
+            Query::And(_) => self.compile_and(),
+            _ => self.compile_nested(),
+        }
+    }
+
+    fn compile_nested(&self) -> anyhow::Result<String> {
+        match self {
+            Query::Term { field, text, phrase } => {
+                let text = utf8_percent_encode(text, TERM);
+                Ok(match phrase {
+                    true => format!("{}:\"{}\"", field.prefix(), text),
+                    false => format!("{}:{}", field.prefix(), text),
+                })
+            }
+            Query::And(_) => Ok(format!("({})", self.compile_and()?)),
+            Query::Or(children) => Ok(format!(
+                "({})",
+                children.iter()
+                    .map(|c| c.compile_nested())
+                    .collect::<anyhow::Result<Vec<String>>>()?
+                    .join("+OR+")
+            )),
+            Query::Not(_) => bail!("NOT can only be used on the right-hand side of AND"),
+            Query::SubmittedDate { from, to } => Ok(format!(
+                "submittedDate:[{}+TO+{}]",
+                from.format("%Y%m%d%H%M"),
+                to.format("%Y%m%d%H%M"),
+            )),
+        }
+    }
+
+    fn compile_and(&self) -> anyhow::Result<String> {
+        let children = match self {
+            Query::And(children) => children,
+            _ => unreachable!(),
+        };
+        let mut q = String::new();
+        for child in children {
+            match (child, q.is_empty()) {
+                (Query::Not(_), true) => bail!("AND needs a positive term before NOT"),
+                (Query::Not(c), false) => q += &format!("+ANDNOT+{}", c.compile_nested()?),
+                (c, true) => q += &c.compile_nested()?,
+                (c, false) => q += &format!("+AND+{}", c.compile_nested()?),
+            }
+        }
+        if q.is_empty() {
+            bail!("Search query is empty");
+        }
+        Ok(q)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Word(String),
+    Phrase(String),
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => bail!("Unterminated phrase in query: {}", input),
+                    }
+                }
+                if phrase.trim().is_empty() {
+                    bail!("Empty phrase in query: {}", input);
+                }
+                tokens.push(Token::Phrase(phrase));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "AND" => tokens.push(Token::And),
+                    "OR" => tokens.push(Token::Or),
+                    "NOT" => tokens.push(Token::Not),
+                    "ANDNOT" => tokens.extend(vec![Token::And, Token::Not]),
+                    _ => tokens.push(Token::Word(word)),
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> anyhow::Result<Query> {
+        let mut children = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            children.push(self.and()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { Query::Or(children) })
+    }
+
+    fn and(&mut self) -> anyhow::Result<Query> {
+        let mut children = vec![self.not()?];
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            children.push(self.not()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { Query::And(children) })
+    }
+
+    fn not(&mut self) -> anyhow::Result<Query> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> anyhow::Result<Query> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let q = self.or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(q),
+                    _ => bail!("Missing `)`"),
+                }
+            }
+            Some(Token::Phrase(text)) => Ok(Query::phrase(Field::All, &text)),
+            Some(Token::Word(word)) => {
+                let (field, text) = match word.find(':') {
+                    Some(i) => (Field::parse(&word[..i])?, &word[i + 1..]),
+                    None => (Field::All, word.as_str()),
+                };
+                if !text.is_empty() {
+                    return Ok(Query::word(field, text));
+                }
+                match self.next() {
+                    Some(Token::Phrase(text)) => Ok(Query::phrase(field, &text)),
+                    _ => bail!("Missing search term after `{}`", word),
+                }
+            }
+            Some(token) => bail!("Unexpected {:?}", token),
+            None => bail!("Unexpected end of query"),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> anyhow::Result<Query> {
+    let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+    let query = parser.or().map_err(|e| anyhow!("Invalid query `{}`: {}", input, e))?;
+    if let Some(token) = parser.peek() {
+        bail!("Invalid query `{}`: unexpected {:?}", input, token);
+    }
+    Ok(query)
+}
+
+#[test]
+fn test_parse_and_compile() {
+    let q = parse(r#"ti:"super resolution" AND (abs:GAN OR abs:diffusion) AND NOT au:Smith"#).unwrap();
+    assert_eq!(
+        q.compile().unwrap(),
+        r#"ti:"super resolution"+AND+(abs:GAN+OR+abs:diffusion)+ANDNOT+au:Smith"#
+    );
+    let q = parse("cat:cs.CV OR (ti:face ANDNOT co:workshop)").unwrap();
+    assert_eq!(q.compile().unwrap(), "(cat:cs.CV+OR+(ti:face+ANDNOT+co:workshop))");
+
+    assert!(parse("foo:bar").is_err());
+    assert!(parse("(ti:face").is_err());
+    assert!(parse("ti:face abs:gan").is_err());
+    assert!(parse("NOT ti:face").unwrap().compile().is_err());
+    assert!(parse("ti:face OR NOT abs:gan").unwrap().compile().is_err());
+    assert!(parse(r#"ti:"super resolution"#).is_err());
+
+    // URLの区切りとして解釈される文字はエンコードする
+    let q = parse(r#"ti:"R&D" AND abs:C++"#).unwrap();
+    assert_eq!(q.compile().unwrap(), r#"ti:"R%26D"+AND+abs:C%2B%2B"#);
+}
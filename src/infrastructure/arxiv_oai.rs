@@ -0,0 +1,514 @@
+use super::arxiv_api::{author_separator, is_accepted, split_authors, split_classes};
+use super::recording;
+use super::retry::{get_text, RetryError, RetryPolicy};
+use crate::domain::arxiv_api as I;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use quick_xml::de::from_str;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::{thread, time};
+
+const DEFAULT_OAI_URL: &str = "https://export.arxiv.org/oai2";
+// OAI-PMHも連続リクエストの間は3秒空ける（混雑時は503とRetry-Afterが返ってくる）
+const OAI_REQUEST_INTERVAL_MILLIS: u64 = 3000;
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OaiAuthor {
+    keyname: String,
+    forenames: Option<String>,
+    suffix: Option<String>,
+    #[serde(default)]
+    affiliation: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OaiAuthors {
+    author: Vec<OaiAuthor>,
+}
+
+// metadataPrefix=arXiv
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ArxivMetadata {
+    id: String,
+    created: String,
+    updated: Option<String>,
+    authors: OaiAuthors,
+    title: String,
+    categories: String,
+    comments: Option<String>,
+    #[serde(rename = "msc-class")]
+    msc_class: Option<String>,
+    #[serde(rename = "acm-class")]
+    acm_class: Option<String>,
+    #[serde(rename = "journal-ref")]
+    journal_ref: Option<String>,
+    doi: Option<String>,
+    #[serde(rename = "abstract")]
+    summary: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct RawVersion {
+    version: String,
+    date: String,
+}
+
+// metadataPrefix=arXivRaw
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ArxivRawMetadata {
+    id: String,
+    version: Vec<RawVersion>,
+    title: String,
+    authors: String,
+    categories: String,
+    comments: Option<String>,
+    #[serde(rename = "msc-class")]
+    msc_class: Option<String>,
+    #[serde(rename = "acm-class")]
+    acm_class: Option<String>,
+    #[serde(rename = "journal-ref")]
+    journal_ref: Option<String>,
+    doi: Option<String>,
+    #[serde(rename = "abstract")]
+    summary: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Metadata {
+    #[serde(rename = "arXiv")]
+    arxiv: Option<ArxivMetadata>,
+    #[serde(rename = "arXivRaw")]
+    arxiv_raw: Option<ArxivRawMetadata>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Header {
+    status: Option<String>,
+    identifier: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Record {
+    header: Header,
+    metadata: Option<Metadata>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ResumptionToken {
+    #[serde(rename = "$value")]
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct ListRecords {
+    #[serde(default)]
+    record: Vec<Record>,
+    #[serde(rename = "resumptionToken")]
+    resumption_token: Option<ResumptionToken>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OaiError {
+    code: String,
+    #[serde(rename = "$value")]
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct OaiPmh {
+    error: Option<OaiError>,
+    #[serde(rename = "ListRecords")]
+    list_records: Option<ListRecords>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataPrefix {
+    Arxiv,
+    ArxivRaw,
+}
+
+impl MetadataPrefix {
+    pub fn parse(prefix: &str) -> anyhow::Result<Self> {
+        match prefix {
+            "arXiv" => Ok(MetadataPrefix::Arxiv),
+            "arXivRaw" => Ok(MetadataPrefix::ArxivRaw),
+            _ => anyhow::bail!("Unknown metadata prefix `{}` (use arXiv or arXivRaw)", prefix),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetadataPrefix::Arxiv => "arXiv",
+            MetadataPrefix::ArxivRaw => "arXivRaw",
+        }
+    }
+}
+
+// カテゴリ(cs.CV, hep-th)が属するOAI-PMHのset(cs, physics:hep-th)
+pub fn set_of_category(category: &str) -> String {
+    let archive = category.split('.').next().unwrap_or(category);
+    match archive {
+        "cs" | "econ" | "eess" | "math" | "q-bio" | "q-fin" | "stat" => archive.to_string(),
+        _ => format!("physics:{}", archive),
+    }
+}
+
+fn normalize_space(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn classes(msc_class: &Option<String>, acm_class: &Option<String>) -> Vec<String> {
+    msc_class.iter().chain(acm_class.iter()).flat_map(|c| split_classes(c)).collect()
+}
+
+fn parse_day(s: &str) -> anyhow::Result<NaiveDateTime> {
+    Ok(NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")?.and_hms(0, 0, 0))
+}
+
+impl ArxivMetadata {
+    fn to_paper(&self) -> anyhow::Result<I::Paper> {
+        let published = parse_day(&self.created)?;
+        let updated = match &self.updated {
+            Some(updated) => parse_day(updated)?,
+            None => published,
+        };
+        let comment = normalize_space(self.comments.as_deref().unwrap_or(""));
+        let name = |a: &OaiAuthor| {
+            let name = match &a.forenames {
+                Some(forenames) => format!("{} {}", forenames, a.keyname),
+                None => a.keyname.clone(),
+            };
+            match &a.suffix {
+                Some(suffix) => normalize_space(&format!("{} {}", name, suffix)),
+                None => normalize_space(&name),
+            }
+        };
+        Ok(I::Paper {
+            title: normalize_space(&self.title),
+            // arXiv形式のメタデータにはバージョンが含まれない
+            url: format!("http://arxiv.org/abs/{}", self.id),
+            pdf_url: format!("http://arxiv.org/pdf/{}", self.id),
+            authors: self.authors.author.iter().map(name).collect(),
+            affiliations: self.authors.author.iter()
+                .flat_map(|a| a.affiliation.iter().map(move |af| I::Affiliation {
+                    author: name(a),
+                    name: normalize_space(af),
+                }))
+                .collect(),
+            classes: classes(&self.msc_class, &self.acm_class),
+            journal_ref: self.journal_ref.as_deref().map(normalize_space),
+            doi: self.doi.as_deref().map(normalize_space),
+            category: self.categories.split_whitespace().next().unwrap_or("").to_string(),
+            categories: self.categories.split_whitespace().map(|c| c.to_string()).collect(),
+            summary: normalize_space(&self.summary),
+            is_accepted: is_accepted(&comment),
+            comment,
+            updated,
+            published,
+        })
+    }
+}
+
+impl ArxivRawMetadata {
+    fn to_paper(&self, author_separator: &Regex) -> anyhow::Result<I::Paper> {
+        let parse_version_date = |v: &RawVersion| -> anyhow::Result<NaiveDateTime> {
+            Ok(DateTime::parse_from_rfc2822(v.date.trim())?.naive_utc())
+        };
+        let (first, last) = match (self.version.first(), self.version.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => anyhow::bail!("{} has no version", self.id),
+        };
+        let comment = normalize_space(self.comments.as_deref().unwrap_or(""));
+        Ok(I::Paper {
+            title: normalize_space(&self.title),
+            url: format!("http://arxiv.org/abs/{}{}", self.id, last.version),
+            pdf_url: format!("http://arxiv.org/pdf/{}{}", self.id, last.version),
+            authors: split_authors(&normalize_space(&self.authors), author_separator),
+            affiliations: vec![],
+            classes: classes(&self.msc_class, &self.acm_class),
+            journal_ref: self.journal_ref.as_deref().map(normalize_space),
+            doi: self.doi.as_deref().map(normalize_space),
+            category: self.categories.split_whitespace().next().unwrap_or("").to_string(),
+            categories: self.categories.split_whitespace().map(|c| c.to_string()).collect(),
+            summary: normalize_space(&self.summary),
+            is_accepted: is_accepted(&comment),
+            comment,
+            updated: parse_version_date(last)?,
+            published: parse_version_date(first)?,
+        })
+    }
+}
+
+// OAI-PMHのListRecordsで論文をまとめて取得する
+#[derive(Debug, Clone)]
+pub struct ArxivOAI {
+    oai_url: String,
+    metadata_prefix: MetadataPrefix,
+    set: Option<String>,
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    resumption_token: Option<String>,
+    retry_policy: RetryPolicy,
+    request_interval: time::Duration,
+    record_dir: Option<PathBuf>,
+    replay_dir: Option<PathBuf>,
+}
+
+impl ArxivOAI {
+    pub fn new(
+        metadata_prefix: MetadataPrefix,
+        set: &Option<String>,
+        from: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            oai_url: DEFAULT_OAI_URL.to_string(),
+            metadata_prefix,
+            set: set.clone(),
+            from,
+            until,
+            resumption_token: None,
+            retry_policy: RetryPolicy::default(),
+            request_interval: time::Duration::from_millis(OAI_REQUEST_INTERVAL_MILLIS),
+            record_dir: None,
+            replay_dir: None,
+        }
+    }
+
+    pub fn oai_url(&mut self, oai_url: &str) -> &mut Self {
+        self.oai_url = oai_url.to_string();
+        self
+    }
+
+    // 中断した取得をこのトークンの位置から再開する
+    pub fn resumption_token(&mut self, resumption_token: Option<String>) -> &mut Self {
+        self.resumption_token = resumption_token;
+        self
+    }
+
+    pub fn request_interval(&mut self, request_interval: time::Duration) -> &mut Self {
+        self.request_interval = request_interval;
+        self
+    }
+
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn record(&mut self, dir: &Path) -> &mut Self {
+        self.record_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    pub fn replay(&mut self, dir: &Path) -> &mut Self {
+        self.replay_dir = Some(dir.to_path_buf());
+        self.request_interval = time::Duration::from_millis(0);
+        self
+    }
+
+    // 途中経過をDBに保存するときのキー
+    pub fn harvest_key(&self) -> String {
+        let day = |d: Option<NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+        format!(
+            "{} {} {} {}",
+            self.metadata_prefix.as_str(),
+            self.set.clone().unwrap_or_default(),
+            day(self.from),
+            day(self.until),
+        )
+    }
+
+    fn generate_oai_url(&self, resumption_token: Option<&str>) -> String {
+        let encode = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+        // resumptionTokenを指定するときは他の引数を指定してはいけない
+        let params = match resumption_token {
+            Some(token) => format!("&resumptionToken={}", encode(token)),
+            None => {
+                let mut params = format!("&metadataPrefix={}", self.metadata_prefix.as_str());
+                if let Some(set) = &self.set {
+                    params += &format!("&set={}", encode(set));
+                }
+                if let Some(from) = self.from {
+                    params += &format!("&from={}", from.format("%Y-%m-%d"));
+                }
+                if let Some(until) = self.until {
+                    params += &format!("&until={}", until.format("%Y-%m-%d"));
+                }
+                params
+            }
+        };
+        format!("{}?verb=ListRecords{}", self.oai_url, params)
+    }
+
+    fn fetch_xml(&self, url: &str) -> Result<String, RetryError> {
+        println!("{}", url);
+        if let Some(dir) = &self.replay_dir {
+            return recording::replay(dir, url).map_err(RetryError::Fatal);
+        }
+        let xml = get_text(url)?;
+        if let Some(dir) = &self.record_dir {
+            recording::record(dir, url, &xml).map_err(RetryError::Fatal)?;
+        }
+        Ok(xml)
+    }
+
+    fn to_oai_pmh(&self, xml: &str) -> anyhow::Result<OaiPmh> {
+        Ok(from_str(xml)?)
+    }
+
+    fn convert(&self, list_records: &ListRecords) -> anyhow::Result<Vec<I::Paper>> {
+        let mut papers = vec![];
+        let author_separator = author_separator();
+        for record in &list_records.record {
+            // 削除された論文はメタデータを持たない
+            if record.header.status.as_deref() == Some("deleted") {
+                continue;
+            }
+            let paper = match &record.metadata {
+                Some(Metadata { arxiv: Some(m), .. }) => m.to_paper(),
+                Some(Metadata { arxiv_raw: Some(m), .. }) => m.to_paper(&author_separator),
+                _ => continue,
+            };
+            papers.push(paper.map_err(|e| e.context(format!("Invalid record {}", record.header.identifier)))?);
+        }
+        Ok(papers)
+    }
+
+    // 1ページ分を取得し、論文と次のページのresumptionTokenを返す
+    pub fn harvest_page(&self, resumption_token: Option<&str>) -> anyhow::Result<(Vec<I::Paper>, Option<String>)> {
+        let url = self.generate_oai_url(resumption_token);
+        let oai_pmh = self.retry_policy.run(|| {
+            let xml = self.fetch_xml(&url)?;
+            self.to_oai_pmh(&xml).map_err(RetryError::Fatal)
+        })?;
+        if let Some(error) = oai_pmh.error {
+            return match error.code.as_str() {
+                // 条件に合う論文が無いのはエラーではない
+                "noRecordsMatch" => Ok((vec![], None)),
+                code => anyhow::bail!(
+                    "OAI-PMH error {}: {}",
+                    code,
+                    error.message.unwrap_or_default()
+                ),
+            };
+        }
+        let list_records = oai_pmh.list_records
+            .ok_or_else(|| anyhow::anyhow!("OAI-PMH response has no ListRecords"))?;
+        let papers = self.convert(&list_records)?;
+        let next = list_records.resumption_token
+            .and_then(|t| t.token)
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        Ok((papers, next))
+    }
+}
+
+impl I::ArxivAPITrait for ArxivOAI {
+    fn query(&self) -> anyhow::Result<Vec<I::Paper>> {
+        let mut papers = vec![];
+        let mut resumption_token = self.resumption_token.clone();
+        loop {
+            let (new_papers, next) = self.harvest_page(resumption_token.as_deref())?;
+            papers.extend(new_papers);
+            resumption_token = next;
+            if resumption_token.is_none() {
+                break;
+            }
+            thread::sleep(self.request_interval);
+        }
+        Ok(papers)
+    }
+}
+
+#[cfg(test)]
+fn mock_arxiv_oai(url: &str, metadata_prefix: MetadataPrefix) -> ArxivOAI {
+    let from = NaiveDate::from_ymd(2026, 10, 1);
+    let mut oai = ArxivOAI::new(metadata_prefix, &Some("cs".to_string()), Some(from), None);
+    oai.oai_url(url)
+        .request_interval(time::Duration::from_millis(0))
+        .retry_policy(RetryPolicy {
+            max_attempts: 3,
+            base_delay: time::Duration::from_millis(0),
+            max_delay: time::Duration::from_millis(0),
+            jitter: time::Duration::from_millis(0),
+        });
+    oai
+}
+
+#[test]
+fn test_harvest() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(200, include_str!("../../tests/fixtures/oai/list_records_1.xml")),
+        MockResponse::new(503, "").header("Retry-After", "0"),
+        MockResponse::new(200, include_str!("../../tests/fixtures/oai/list_records_2.xml")),
+    ]);
+    let papers = mock_arxiv_oai(&server.url(), MetadataPrefix::Arxiv).query().unwrap();
+    assert_eq!(papers.len(), 2);
+    assert_eq!(papers[0].title, "Harvesting Papers at Scale");
+    assert_eq!(papers[0].url, "http://arxiv.org/abs/2610.01234");
+    assert_eq!(papers[0].authors, vec!["Taro Yamada", "Jane Doe"]);
+    assert_eq!(papers[0].category, "cs.DL");
+    assert_eq!(papers[0].categories, vec!["cs.DL", "cs.IR"]);
+    assert_eq!(papers[0].classes, vec!["H.3.7", "H.3.3"]);
+    assert_eq!(papers[0].journal_ref.as_deref(), Some("JCDL 2026"));
+    assert_eq!(papers[0].doi.as_deref(), Some("10.1000/jcdl.2026.1"));
+    assert_eq!(papers[0].affiliations, vec![I::Affiliation {
+        author: "Taro Yamada".to_string(),
+        name: "Univ. of Tokyo".to_string(),
+    }]);
+    assert_eq!(papers[0].summary, "We harvest papers.");
+    assert!(papers[0].is_accepted);
+    assert_eq!(papers[0].updated, NaiveDate::from_ymd(2026, 10, 2).and_hms(0, 0, 0));
+    assert_eq!(papers[1].authors, vec!["John Smith Jr"]);
+    assert_eq!(papers[1].updated, papers[1].published);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].ends_with("?verb=ListRecords&metadataPrefix=arXiv&set=cs&from=2026-10-01"));
+    assert!(requests[2].ends_with("?verb=ListRecords&resumptionToken=6960524%7C1001"));
+
+    // 保存しておいたresumptionTokenから再開する
+    let server = MockServer::start(vec![
+        MockResponse::new(200, include_str!("../../tests/fixtures/oai/list_records_2.xml")),
+    ]);
+    let mut oai = mock_arxiv_oai(&server.url(), MetadataPrefix::Arxiv);
+    let papers = oai.resumption_token(Some("6960524|1001".to_string())).query().unwrap();
+    assert_eq!(papers.len(), 1);
+    assert_eq!(server.requests().len(), 1);
+
+    let server = MockServer::start(vec![
+        MockResponse::new(200, include_str!("../../tests/fixtures/oai/no_records.xml")),
+    ]);
+    let papers = mock_arxiv_oai(&server.url(), MetadataPrefix::Arxiv).query().unwrap();
+    assert!(papers.is_empty());
+}
+
+#[test]
+fn test_harvest_arxiv_raw() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(200, include_str!("../../tests/fixtures/oai/list_records_raw.xml")),
+    ]);
+    let papers = mock_arxiv_oai(&server.url(), MetadataPrefix::ArxivRaw).query().unwrap();
+    assert_eq!(papers.len(), 1);
+    assert_eq!(papers[0].url, "http://arxiv.org/abs/2610.01234v2");
+    assert_eq!(papers[0].authors, vec!["Taro Yamada", "Jane Doe", "John Smith"]);
+    assert_eq!(papers[0].classes, vec!["68T45"]);
+    assert_eq!(papers[0].doi.as_deref(), Some("10.1000/jcdl.2026.1"));
+    assert_eq!(papers[0].published, NaiveDate::from_ymd(2026, 10, 1).and_hms(17, 59, 59));
+    assert_eq!(papers[0].updated, NaiveDate::from_ymd(2026, 10, 2).and_hms(9, 0, 0));
+    assert!(server.requests()[0].contains("metadataPrefix=arXivRaw"));
+}
+
+#[test]
+fn test_set_of_category() {
+    assert_eq!(set_of_category("cs.CV"), "cs");
+    assert_eq!(set_of_category("q-bio.NC"), "q-bio");
+    assert_eq!(set_of_category("hep-th"), "physics:hep-th");
+    assert_eq!(set_of_category("physics.optics"), "physics:physics");
+}
@@ -6,13 +6,20 @@ use std::path::Iter;
 use std::collections::{HashSet, HashMap};
 use std::iter::FromIterator;
 
-use super::sqlite::SQLite;
-use crate::db::models::{PaperAuthor, Paper, Category, Author};
+use super::arxiv_api::is_accepted;
+use super::database::{insert_or_ignore, Database, DbConnection};
+use super::pdf_library::{default_library, PdfLibrary};
+use crate::db::models::{PaperAuthor, Paper, PaperCount, PaperFile, PaperLink, PaperVenue, PaperVersion, Category, Author};
 use crate::db::schema::*;
 use crate::domain::arxiv_paper as I;
 use crate::domain::arxiv_paper::PaperId;
+use crate::domain::arxiv_id::ArxivId;
+use crate::domain::author_name::find_followed;
+use crate::domain::paper_counts::PaperCounts;
+use crate::domain::paper_link as L;
+use crate::domain::venue::{AcceptanceStatus, Track, Venue};
 
-#[derive(Insertable, Debug)]
+#[derive(Insertable, AsChangeset, Debug)]
 #[table_name = "papers"]
 struct NewPaper {
     pub title: String,
@@ -24,6 +31,9 @@ struct NewPaper {
     pub summary: String,
     pub comment: String,
     pub accepted: i32,
+    pub journal_ref: Option<String>,
+    pub doi: Option<String>,
+    pub arxiv_id: Option<String>,
 }
 
 #[derive(Insertable, Debug)]
@@ -45,135 +55,563 @@ struct NewPaperAuthor {
     pub author_id: i32,
 }
 
+#[derive(Insertable, Debug)]
+#[table_name = "paper_categories"]
+struct NewPaperCategory {
+    pub paper_id: i32,
+    pub category_id: i32,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_affiliations"]
+struct NewPaperAffiliation {
+    pub paper_id: i32,
+    pub author_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_classes"]
+struct NewPaperClass {
+    pub paper_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_counts"]
+struct NewPaperCount {
+    pub paper_id: i32,
+    pub pages: Option<i32>,
+    pub figures: Option<i32>,
+    pub tables: Option<i32>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_links"]
+struct NewPaperLink {
+    pub paper_id: i32,
+    pub kind: String,
+    pub url: String,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_venues"]
+struct NewPaperVenue {
+    pub paper_id: i32,
+    pub name: String,
+    pub year: Option<i32>,
+    pub track: Option<String>,
+    pub status: String,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_versions"]
+struct NewPaperVersion {
+    pub paper_id: i32,
+    pub version: i32,
+    pub title: String,
+    pub summary: String,
+    pub comment: String,
+    pub updated: NaiveDateTime,
+}
+
+// 論文ごとのクロスリスト先を含む全てのカテゴリ
+pub(crate) fn find_categories(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, Vec<I::Category>>> {
+    let paper_categories: Vec<(i32, Category)> = with_connection!(db, |conn| paper_categories::table
+        .inner_join(categories::table)
+        .filter(paper_categories::paper_id.eq_any(paper_ids))
+        .select((paper_categories::paper_id, categories::all_columns))
+        .load(conn))?;
+    let mut categories_map: HashMap<i32, Vec<I::Category>> = HashMap::new();
+    for (paper_id, c) in paper_categories {
+        categories_map.entry(paper_id).or_default()
+            .push(I::Category { id: c.id, name: c.name });
+    }
+    Ok(categories_map)
+}
+
+// 論文ごとの著者と、その論文での所属
+pub(crate) fn find_authors(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, Vec<I::Author>>> {
+    let paper_authors: Vec<(i32, Author)> = with_connection!(db, |conn| paper_authors::table
+        .inner_join(authors::table)
+        .filter(paper_authors::paper_id.eq_any(paper_ids))
+        .select((paper_authors::paper_id, authors::all_columns))
+        .load(conn))?;
+    let affiliations_map = find_affiliations(db, paper_ids)?;
+    let mut authors_map: HashMap<i32, Vec<I::Author>> = HashMap::new();
+    for (paper_id, a) in paper_authors {
+        authors_map.entry(paper_id).or_default().push(I::Author {
+            affiliations: affiliations_map.get(&(paper_id, a.id)).cloned().unwrap_or_default(),
+            id: a.id,
+            name: a.name,
+        });
+    }
+    Ok(authors_map)
+}
+
+pub(crate) fn find_classes(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, Vec<String>>> {
+    let paper_classes: Vec<(i32, String)> = with_connection!(db, |conn| paper_classes::table
+        .filter(paper_classes::paper_id.eq_any(paper_ids))
+        .select((paper_classes::paper_id, paper_classes::name))
+        .load(conn))?;
+    let mut classes_map: HashMap<i32, Vec<String>> = HashMap::new();
+    for (paper_id, name) in paper_classes {
+        classes_map.entry(paper_id).or_default().push(name);
+    }
+    Ok(classes_map)
+}
+
+// (論文, 著者) ごとの所属
+pub(crate) fn find_affiliations(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<(i32, i32), Vec<String>>> {
+    let paper_affiliations: Vec<(i32, i32, String)> = with_connection!(db, |conn| paper_affiliations::table
+        .filter(paper_affiliations::paper_id.eq_any(paper_ids))
+        .select((paper_affiliations::paper_id, paper_affiliations::author_id, paper_affiliations::name))
+        .load(conn))?;
+    let mut affiliations_map: HashMap<(i32, i32), Vec<String>> = HashMap::new();
+    for (paper_id, author_id, name) in paper_affiliations {
+        affiliations_map.entry((paper_id, author_id)).or_default().push(name);
+    }
+    Ok(affiliations_map)
+}
+
+pub(crate) fn find_venues(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, Venue>> {
+    let paper_venues: Vec<PaperVenue> = with_connection!(db, |conn| paper_venues::table
+        .filter(paper_venues::paper_id.eq_any(paper_ids))
+        .load(conn))?;
+    Ok(paper_venues.into_iter().map(|v| (v.paper_id, Venue {
+        name: v.name,
+        year: v.year,
+        track: v.track.as_deref().and_then(Track::parse),
+        status: AcceptanceStatus::parse(&v.status).unwrap_or(AcceptanceStatus::Unknown),
+    })).collect())
+}
+
+pub(crate) fn find_links(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, Vec<L::PaperLink>>> {
+    let paper_links: Vec<PaperLink> = with_connection!(db, |conn| paper_links::table
+        .filter(paper_links::paper_id.eq_any(paper_ids))
+        .load(conn))?;
+    let mut links_map: HashMap<i32, Vec<L::PaperLink>> = HashMap::new();
+    for l in paper_links {
+        if let Some(kind) = L::LinkKind::parse(&l.kind) {
+            links_map.entry(l.paper_id).or_default()
+                .push(L::PaperLink { kind, url: l.url });
+        }
+    }
+    Ok(links_map)
+}
+
+pub(crate) fn find_counts(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, PaperCounts>> {
+    let paper_counts: Vec<PaperCount> = with_connection!(db, |conn| paper_counts::table
+        .filter(paper_counts::paper_id.eq_any(paper_ids))
+        .load(conn))?;
+    Ok(paper_counts.into_iter().map(|c| (c.paper_id, PaperCounts {
+        pages: c.pages.map(|n| n as u32),
+        figures: c.figures.map(|n| n as u32),
+        tables: c.tables.map(|n| n as u32),
+    })).collect())
+}
+
+pub(crate) fn find_versions(db: &Database, paper_ids: &[i32]) -> QueryResult<HashMap<i32, Vec<I::PaperVersion>>> {
+    let paper_versions: Vec<PaperVersion> = with_connection!(db, |conn| paper_versions::table
+        .filter(paper_versions::paper_id.eq_any(paper_ids))
+        .order((paper_versions::paper_id, paper_versions::version))
+        .load(conn))?;
+    let mut versions_map: HashMap<i32, Vec<I::PaperVersion>> = HashMap::new();
+    for v in paper_versions {
+        versions_map.entry(v.paper_id).or_default()
+            .push(I::PaperVersion {
+                version: v.version as u32,
+                is_accepted: is_accepted(&v.comment),
+                title: v.title,
+                summary: v.summary,
+                comment: v.comment,
+                updated: v.updated,
+            });
+    }
+    Ok(versions_map)
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "paper_files"]
+struct NewPaperFile {
+    pub paper_id: i32,
+    pub version: i32,
+    pub path: String,
+    pub size: i64,
+    pub sha256: String,
+}
 
 #[derive(Clone)]
-pub struct ArxivPaperRepository {}
+pub struct ArxivPaperRepository {
+    conn: DbConnection,
+    library: PdfLibrary,
+}
 
 impl ArxivPaperRepository {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(conn: DbConnection) -> Self {
+        Self::with_library(conn, PdfLibrary::new(&default_library()))
+    }
+
+    // downloadでPDFを置くディレクトリを指定する
+    pub fn with_library(conn: DbConnection, library: PdfLibrary) -> Self {
+        Self { conn, library }
+    }
+
+    // 読み込んだ行に、著者やカテゴリなど別のテーブルにある情報をまとめて加える
+    fn to_papers(&self, load_papers: Vec<(Paper, Category)>) -> anyhow::Result<Vec<I::Paper>> {
+        let paper_ids: Vec<i32> = load_papers.iter().map(|p| p.0.id).collect();
+        let authors_map = find_authors(&self.conn, &paper_ids)?;
+        let categories_map = find_categories(&self.conn, &paper_ids)?;
+        let classes_map = find_classes(&self.conn, &paper_ids)?;
+        let versions_map = find_versions(&self.conn, &paper_ids)?;
+        let venues_map = find_venues(&self.conn, &paper_ids)?;
+        let links_map = find_links(&self.conn, &paper_ids)?;
+        let counts_map = find_counts(&self.conn, &paper_ids)?;
+        Ok(load_papers.iter().map(|p| I::Paper{
+            id: PaperId(p.0.id),
+            arxiv_id: ArxivId::parse(&p.0.url).ok(),
+            title: p.0.title.to_string(),
+            url: p.0.url.to_string(),
+            pdf_url: p.0.pdf_url.to_string(),
+            authors: authors_map.get(&p.0.id).cloned().unwrap_or_default(),
+            category: I::Category {
+                id: p.1.id,
+                name: p.1.name.to_string(),
+            },
+            categories: categories_map.get(&p.0.id).cloned().unwrap_or_default(),
+            summary: p.0.summary.to_string(),
+            comment: p.0.comment.to_string(),
+            is_accepted: p.0.accepted > 0,
+            journal_ref: p.0.journal_ref.clone(),
+            doi: p.0.doi.clone(),
+            classes: classes_map.get(&p.0.id).cloned().unwrap_or_default(),
+            venue: venues_map.get(&p.0.id).cloned(),
+            links: links_map.get(&p.0.id).cloned().unwrap_or_default(),
+            counts: counts_map.get(&p.0.id).cloned().unwrap_or_default(),
+            versions: versions_map.get(&p.0.id).cloned().unwrap_or_default(),
+            updated: p.0.updated,
+            published: p.0.published,
+        }).collect())
+    }
+
+    fn save_file(&self, file: &I::PaperFile) -> anyhow::Result<()> {
+        let new_file = NewPaperFile {
+            paper_id: file.paper_id.0,
+            version: file.version as i32,
+            path: file.path.clone(),
+            size: file.size as i64,
+            sha256: file.sha256.clone(),
+        };
+        // ファイルが消えていてダウンロードし直した場合は記録を置き換える
+        with_connection!(self.conn, |conn| conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(paper_files::table.find((new_file.paper_id, new_file.version))).execute(conn)?;
+            diesel::insert_into(paper_files::table).values(&new_file).execute(conn)?;
+            Ok(())
+        }))?;
+        Ok(())
     }
 }
 
 impl I::ArxivPaperRepositoryTrait for ArxivPaperRepository {
     fn find_by_id(&self, id: I::PaperId) -> anyhow::Result<Option<I::Paper>> {
-        let conn = SQLite::create().connect();
-        let paper = conn.transaction::<Option<I::Paper>, _, _>(|| {
+        let paper = with_connection!(self.conn, |conn| conn.transaction::<Option<I::Paper>, _, _>(|| {
             // joinすれば一発だが、[(paper1, author1), (paper1, author2), (paper1, author3)]となり
             // 後処理が複雑化するのと、関数化しづらくなるため敢えて分割している
             let paper_with_category: (Paper, Category) = papers::table
                 .find(id.0)
                 .inner_join(categories::table)
                 .select((papers::all_columns, categories::all_columns))
-                .first(&conn)?;
+                .first(conn)?;
             let authors: Vec<Author> = paper_authors::table
                 .filter(paper_authors::paper_id.eq(id.0))
                 .inner_join(authors::table)
                 .select(authors::all_columns)
-                .load(&conn)?;
+                .load(conn)?;
+            let categories = find_categories(&self.conn, &[id.0])?.remove(&id.0).unwrap_or_default();
+            let classes = find_classes(&self.conn, &[id.0])?.remove(&id.0).unwrap_or_default();
+            let affiliations_map = find_affiliations(&self.conn, &[id.0])?;
+            let versions = find_versions(&self.conn, &[id.0])?.remove(&id.0).unwrap_or_default();
+            let venue = find_venues(&self.conn, &[id.0])?.remove(&id.0);
+            let links = find_links(&self.conn, &[id.0])?.remove(&id.0).unwrap_or_default();
+            let counts = find_counts(&self.conn, &[id.0])?.remove(&id.0).unwrap_or_default();
             Ok(Some(I::Paper {
                 id: I::PaperId(paper_with_category.0.id),
+                arxiv_id: ArxivId::parse(&paper_with_category.0.url).ok(),
                 title: paper_with_category.0.title,
                 updated: paper_with_category.0.updated,
                 published: paper_with_category.0.published,
                 url: paper_with_category.0.url,
                 pdf_url: paper_with_category.0.pdf_url,
-                authors: authors.iter().map(
-                    |a| I::Author { id: a.id, name: a.name.to_owned() }).collect(),
+                authors: authors.iter().map(|a| I::Author {
+                    id: a.id,
+                    name: a.name.to_owned(),
+                    affiliations: affiliations_map.get(&(id.0, a.id)).cloned().unwrap_or_default(),
+                }).collect(),
                 category: I::Category {
                     id: paper_with_category.1.id,
                     name: paper_with_category.1.name,
                 },
+                categories,
                 comment: paper_with_category.0.comment,
                 is_accepted: paper_with_category.0.accepted > 0,
+                journal_ref: paper_with_category.0.journal_ref,
+                doi: paper_with_category.0.doi,
+                classes,
+                venue,
+                links,
+                counts,
+                versions,
                 summary: paper_with_category.0.summary,
             }))
-        });
+        }));
         paper
     }
     fn find_by_urls(&self, urls: &Vec<String>) -> anyhow::Result<Vec<I::Paper>> {
-        let conn = SQLite::create().connect();
-        let load_papers: Vec<(Paper, Category)> = papers::table
+        let load_papers: Vec<(Paper, Category)> = with_connection!(self.conn, |conn| papers::table
             .inner_join(categories::table)
             .filter(papers::url.eq_any(urls))
+            .order(papers::id)
             .select((papers::all_columns, categories::all_columns))
-            .load(&conn)?;
-        Ok(load_papers.iter().map(|p| I::Paper{
-            id: PaperId(p.0.id),
-            title: p.0.title.to_string(),
-            url: p.0.url.to_string(),
-            pdf_url: p.0.pdf_url.to_string(),
-            authors: vec![],
-            category: I::Category {
-                id: p.1.id,
-                name: p.1.name.to_string(),
-            },
-            summary: p.0.summary.to_string(),
-            comment: p.0.comment.to_string(),
-            is_accepted: p.0.accepted > 0,
-            updated: p.0.updated,
-            published: p.0.published,
-        }).collect())
+            .load(conn))?;
+        self.to_papers(load_papers)
     }
 
-    fn save(&self, papers: &Vec<I::NewPaper>) -> anyhow::Result<usize> {
-        let conn = SQLite::create().connect();
-        let res = conn.transaction::<_, _, _>(|| {
+    fn find_by_ids(&self, ids: &[I::PaperId]) -> anyhow::Result<Vec<I::Paper>> {
+        let ids: Vec<i32> = ids.iter().map(|id| id.0).collect();
+        let load_papers: Vec<(Paper, Category)> = with_connection!(self.conn, |conn| papers::table
+            .inner_join(categories::table)
+            .filter(papers::id.eq_any(&ids))
+            .order(papers::id)
+            .select((papers::all_columns, categories::all_columns))
+            .load(conn))?;
+        self.to_papers(load_papers)
+    }
+
+    fn find_by_arxiv_ids(&self, arxiv_ids: &[String]) -> anyhow::Result<Vec<I::Paper>> {
+        let load_papers: Vec<(Paper, Category)> = with_connection!(self.conn, |conn| papers::table
+            .inner_join(categories::table)
+            .filter(papers::arxiv_id.eq_any(arxiv_ids))
+            .order(papers::id)
+            .select((papers::all_columns, categories::all_columns))
+            .load(conn))?;
+        self.to_papers(load_papers)
+    }
+
+    fn save(&self, all_versions: &Vec<I::NewPaper>) -> anyhow::Result<usize> {
+        let res = with_connection!(self.conn, |conn| conn.transaction::<_, _, _>(|| {
+            // 同じ論文の複数のバージョンが含まれる場合は最新のものだけを保存する
+            let papers = &latest_versions(all_versions);
             // Insert authors
             let new_authors = new_authors(&papers);
             // 未知のauthorのみinsert
-            diesel::insert_or_ignore_into(authors::table)
-                .values(&new_authors)
-                .execute(&conn)?;
+            insert_or_ignore(conn, authors::table, &new_authors)?;
             let authors_map: HashMap<String, i32> = authors::table
                 .filter(authors::name.eq_any(
                     new_authors.iter()
                         .map(|a| a.name.clone())
                         .collect::<Vec<String>>()))
                 .select((authors::name, authors::id))
-                .load(&conn)?
+                .load(conn)?
                 .into_iter().collect();
             // Insert categories
             let new_categories = new_categories(&papers);
-            diesel::insert_or_ignore_into(categories::table)
-                .values(&new_categories)
-                .execute(&conn)?;
+            insert_or_ignore(conn, categories::table, &new_categories)?;
             let categories_map: HashMap<String, i32> = categories::table
                 .filter(categories::name.eq_any(
                     new_categories.iter()
                         .map(|c| c.name.clone())
                         .collect::<Vec<String>>()))
                 .select((categories::name, categories::id))
-                .load(&conn)?
+                .load(conn)?
                 .into_iter().collect();
 
+            // 保存済みの論文はバージョンを除いたarXiv IDで探す
+            let stored: Vec<Paper> = papers::table
+                .filter(papers::arxiv_id.eq_any(
+                    papers.iter()
+                        .filter_map(|p| p.arxiv_id.as_ref().map(|id| id.id.clone()))
+                        .collect::<Vec<String>>()
+                ))
+                .order(papers::id)
+                .load(conn)?;
+            // バージョン違いで別々に保存された過去の行がある場合はバージョンが新しい方を使う
+            // 古いバージョンの行を上書きすると、新しいバージョンの行とURLが重複してしまう
+            let mut stored: Vec<(Option<u32>, Paper)> = stored.into_iter()
+                .map(|p| (ArxivId::parse(&p.url).ok().and_then(|id| id.version), p))
+                .collect();
+            stored.sort_by_key(|(version, p)| (*version, p.updated));
+            let stored_map: HashMap<String, Paper> = stored.into_iter()
+                .filter_map(|(_, p)| Some((p.arxiv_id.clone()?, p)))
+                .collect();
+            let (existing, unknown): (Vec<I::NewPaper>, Vec<I::NewPaper>) = papers.iter().cloned()
+                .partition(|p| matches!(&p.arxiv_id, Some(id) if stored_map.contains_key(&id.id)));
+
             // Insert papers
-            let new_papers = new_papers(&papers, &categories_map);
-            let n_inserted = diesel::insert_or_ignore_into(papers::table)
-                .values(&new_papers)
-                .execute(&conn)?;
-            let papers_map: HashMap<String, i32> = papers::table
+            let new_papers = new_papers(&unknown, &categories_map);
+            let n_inserted = insert_or_ignore(conn, papers::table, &new_papers)?;
+            let mut papers_map: HashMap<String, i32> = papers::table
                 .filter(papers::url.eq_any(
                     new_papers.iter()
                         .map(|p| p.url.clone())
                         .collect::<Vec<String>>()
                 ))
                 .select((papers::url, papers::id))
-                .load(&conn)?
+                .load(conn)?
                 .into_iter().collect();
 
+            // Update papers
+            // 新しいバージョンの場合だけ既存の行を上書きする
+            let updated: Vec<I::NewPaper> = existing.iter()
+                .filter(|p| is_newer(p, &stored_map[&p.arxiv_id.as_ref().unwrap().id]))
+                .cloned()
+                .collect();
+            // 上書きしない場合も、保存済みの行に欠けているjournal_refやDOIは補う
+            for p in existing.iter().filter(|p| !updated.iter().any(|u| u.url == p.url)) {
+                let stored = &stored_map[&p.arxiv_id.as_ref().unwrap().id];
+                let journal_ref = stored.journal_ref.clone().or_else(|| p.journal_ref.clone());
+                let doi = stored.doi.clone().or_else(|| p.doi.clone());
+                if journal_ref != stored.journal_ref || doi != stored.doi {
+                    diesel::update(papers::table.find(stored.id))
+                        .set((papers::journal_ref.eq(journal_ref), papers::doi.eq(doi)))
+                        .execute(conn)?;
+                }
+            }
+            let update_papers = self::new_papers(&updated, &categories_map);
+            for (p, new_paper) in updated.iter().zip(update_papers) {
+                let paper_id = stored_map[&p.arxiv_id.as_ref().unwrap().id].id;
+                diesel::update(papers::table.find(paper_id))
+                    .set(&new_paper)
+                    .execute(conn)?;
+                papers_map.insert(p.url.clone(), paper_id);
+            }
+            // 著者やカテゴリは新しいバージョンのもので置き換える
+            let updated_ids: Vec<i32> = updated.iter().map(|p| papers_map[&p.url]).collect();
+            diesel::delete(paper_authors::table.filter(paper_authors::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            diesel::delete(paper_categories::table.filter(paper_categories::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            diesel::delete(paper_affiliations::table.filter(paper_affiliations::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            diesel::delete(paper_classes::table.filter(paper_classes::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            diesel::delete(paper_venues::table.filter(paper_venues::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            diesel::delete(paper_links::table.filter(paper_links::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            diesel::delete(paper_counts::table.filter(paper_counts::paper_id.eq_any(&updated_ids)))
+                .execute(conn)?;
+            let papers: Vec<I::NewPaper> = unknown.into_iter().chain(updated)
+                .filter(|p| papers_map.contains_key(&p.url))
+                .collect();
+            let papers = &papers;
+
             // Insert paper_authors
             let new_paper_authors = new_paper_authors(&papers, &papers_map, &authors_map);
-            diesel::insert_or_ignore_into(paper_authors::table)
-                .values(&new_paper_authors)
-                .execute(&conn);
-            Ok(n_inserted)
-        });
+            insert_or_ignore(conn, paper_authors::table, &new_paper_authors)?;
+
+            // Insert paper_categories
+            let new_paper_categories = new_paper_categories(papers, &papers_map, &categories_map);
+            insert_or_ignore(conn, paper_categories::table, &new_paper_categories)?;
+
+            // Insert paper_affiliations
+            let new_paper_affiliations = new_paper_affiliations(papers, &papers_map, &authors_map);
+            insert_or_ignore(conn, paper_affiliations::table, &new_paper_affiliations)?;
+
+            // Insert paper_classes
+            let new_paper_classes = new_paper_classes(papers, &papers_map);
+            insert_or_ignore(conn, paper_classes::table, &new_paper_classes)?;
+
+            // Insert paper_venues
+            let new_paper_venues = new_paper_venues(papers, &papers_map);
+            insert_or_ignore(conn, paper_venues::table, &new_paper_venues)?;
+
+            // Insert paper_links
+            let new_paper_links = new_paper_links(papers, &papers_map);
+            insert_or_ignore(conn, paper_links::table, &new_paper_links)?;
+
+            // Insert paper_counts
+            let new_paper_counts = new_paper_counts(papers, &papers_map);
+            insert_or_ignore(conn, paper_counts::table, &new_paper_counts)?;
+
+            // Insert paper_versions
+            // 古いバージョンしか取得できなかった場合も履歴としては残す
+            let ids_map: HashMap<String, i32> = papers::table
+                .filter(papers::arxiv_id.eq_any(
+                    all_versions.iter()
+                        .filter_map(|p| p.arxiv_id.as_ref().map(|id| id.id.clone()))
+                        .collect::<Vec<String>>()
+                ))
+                .order(papers::id)
+                .select((papers::arxiv_id, papers::id))
+                .load::<(Option<String>, i32)>(conn)?
+                .into_iter()
+                .filter_map(|(arxiv_id, id)| Some((arxiv_id?, id)))
+                .collect();
+            let new_paper_versions = new_paper_versions(all_versions, &ids_map);
+            insert_or_ignore(conn, paper_versions::table, &new_paper_versions)?;
+            Ok(n_inserted + updated_ids.len())
+        }));
         res
     }
 
-    fn download(&self, papers: &Vec<I::Paper>) -> bool {
-        unimplemented!()
+    fn find_files(&self, ids: &[I::PaperId]) -> anyhow::Result<Vec<I::PaperFile>> {
+        let files: Vec<PaperFile> = with_connection!(self.conn, |conn| paper_files::table
+            .filter(paper_files::paper_id.eq_any(ids.iter().map(|id| id.0).collect::<Vec<i32>>()))
+            .order((paper_files::paper_id, paper_files::version))
+            .load(conn))?;
+        Ok(files.into_iter().map(|f| I::PaperFile {
+            paper_id: PaperId(f.paper_id),
+            version: f.version as u32,
+            path: f.path,
+            size: f.size as u64,
+            sha256: f.sha256,
+        }).collect())
+    }
+
+    fn find_author_names(&self, follow_authors: &[String]) -> anyhow::Result<Vec<String>> {
+        // アクセント記号などはSQLでは比べにくいので、名前を読み込んでから照合する
+        let names: Vec<String> = with_connection!(self.conn, |conn| authors::table
+            .select(authors::name)
+            .order(authors::name)
+            .load(conn))?;
+        Ok(find_followed(&names, follow_authors).into_iter().cloned().collect())
+    }
+
+    fn download(&self, papers: &Vec<I::Paper>) -> anyhow::Result<Vec<I::PaperFile>> {
+        let mut stored = self.find_files(&papers.iter().map(|p| p.id.clone()).collect::<Vec<PaperId>>())?;
+        let mut downloaded = vec![];
+        for p in papers {
+            let (arxiv_id, version) = match (&p.arxiv_id, p.current_version()) {
+                (Some(arxiv_id), Some(version)) => (arxiv_id, version),
+                _ => {
+                    eprintln!("Skipped {}: the arXiv version is unknown", &p.url);
+                    continue;
+                }
+            };
+            // 同じバージョンをダウンロード済みで、ファイルも残っていればダウンロードしない
+            if stored.iter().any(|f| f.paper_id == p.id && f.version == version && self.library.exists(&f.path)) {
+                continue;
+            }
+            // 一つの論文の失敗で残りの論文がダウンロードされなくなるのを防ぐ
+            let pdf = match self.library.fetch(&arxiv_id.id, version) {
+                Ok(pdf) => pdf,
+                Err(e) => {
+                    eprintln!("Failed to download {}: {:?}", &p.url, e);
+                    continue;
+                }
+            };
+            let file = I::PaperFile {
+                paper_id: p.id.clone(),
+                version,
+                path: pdf.path,
+                size: pdf.size,
+                sha256: pdf.sha256,
+            };
+            self.save_file(&file)?;
+            stored.push(file.clone());
+            downloaded.push(file);
+        }
+        Ok(downloaded)
     }
 }
 
@@ -191,7 +629,8 @@ fn new_authors(papers: &Vec<I::NewPaper>) -> Vec<NewAuthor> {
 fn new_categories(papers: &Vec<I::NewPaper>) -> Vec<NewCategory> {
     let mut new_categories: Vec<&str> = papers
         .iter()
-        .map(|p| p.category.name.as_ref())
+        .flat_map(|p| std::iter::once(&p.category).chain(p.categories.iter()))
+        .map(|c| c.name.as_ref())
         .collect();
     new_categories.sort_unstable();
     new_categories.dedup();
@@ -210,9 +649,44 @@ fn new_papers(papers: &Vec<I::NewPaper>, category_map: &HashMap<String, i32>) ->
             summary: p.summary.to_owned(),
             comment: p.comment.to_owned(),
             accepted: p.is_accepted as i32,
+            journal_ref: p.journal_ref.to_owned(),
+            doi: p.doi.to_owned(),
+            arxiv_id: p.arxiv_id.as_ref().map(|id| id.id.to_owned()),
         }).collect()
 }
 
+fn version(paper: &I::NewPaper) -> Option<u32> {
+    paper.arxiv_id.as_ref().and_then(|id| id.version)
+}
+
+pub(crate) fn latest_versions(papers: &[I::NewPaper]) -> Vec<I::NewPaper> {
+    let mut latest: Vec<I::NewPaper> = vec![];
+    for p in papers {
+        let same = p.arxiv_id.as_ref().and_then(|id| latest.iter()
+            .position(|l| l.arxiv_id.as_ref().map(|l| &l.id) == Some(&id.id)));
+        match same {
+            Some(i) => {
+                if (version(p), p.updated) > (version(&latest[i]), latest[i].updated) {
+                    latest[i] = p.clone();
+                }
+            }
+            None => latest.push(p.clone()),
+        }
+    }
+    latest
+}
+
+// バージョンが分かる場合はバージョンで、分からない場合は更新日時で比べる
+// バージョンの分からないレコード(OAIなど)では、バージョンの分かる行を置き換えない
+fn is_newer(paper: &I::NewPaper, stored: &Paper) -> bool {
+    let stored_version = ArxivId::parse(&stored.url).ok().and_then(|id| id.version);
+    match (version(paper), stored_version) {
+        (Some(new), Some(old)) => new > old,
+        (None, Some(_)) => false,
+        _ => paper.updated > stored.updated,
+    }
+}
+
 fn new_paper_authors(papers: &Vec<I::NewPaper>,
                      papers_map: &HashMap<String, i32>,
                      authors_map: &HashMap<String, i32>,
@@ -224,4 +698,250 @@ fn new_paper_authors(papers: &Vec<I::NewPaper>,
                 author_id: authors_map.get(&a.name).unwrap().to_owned(),
             }
     )).flatten().collect()
-}
\ No newline at end of file
+}
+
+fn new_paper_categories(papers: &[I::NewPaper],
+                        papers_map: &HashMap<String, i32>,
+                        categories_map: &HashMap<String, i32>,
+) -> Vec<NewPaperCategory> {
+    let mut new_paper_categories: Vec<NewPaperCategory> = vec![];
+    for p in papers {
+        let paper_id = papers_map.get(&p.url).unwrap().to_owned();
+        // メインカテゴリも必ず登録する
+        let mut category_ids: Vec<i32> = std::iter::once(&p.category).chain(p.categories.iter())
+            .map(|c| categories_map.get(&c.name).unwrap().to_owned())
+            .collect();
+        category_ids.sort_unstable();
+        category_ids.dedup();
+        new_paper_categories.extend(category_ids.into_iter()
+            .map(|category_id| NewPaperCategory { paper_id, category_id }));
+    }
+    new_paper_categories
+}
+
+fn new_paper_affiliations(papers: &[I::NewPaper],
+                          papers_map: &HashMap<String, i32>,
+                          authors_map: &HashMap<String, i32>,
+) -> Vec<NewPaperAffiliation> {
+    let mut new_paper_affiliations = vec![];
+    for p in papers {
+        let paper_id = papers_map.get(&p.url).unwrap().to_owned();
+        for a in &p.authors {
+            let author_id = authors_map.get(&a.name).unwrap().to_owned();
+            new_paper_affiliations.extend(a.affiliations.iter()
+                .map(|name| NewPaperAffiliation { paper_id, author_id, name: name.to_owned() }));
+        }
+    }
+    new_paper_affiliations
+}
+
+fn new_paper_classes(papers: &[I::NewPaper], papers_map: &HashMap<String, i32>) -> Vec<NewPaperClass> {
+    papers.iter().flat_map(|p| {
+        let paper_id = papers_map.get(&p.url).unwrap().to_owned();
+        p.classes.iter().map(move |name| NewPaperClass { paper_id, name: name.to_owned() })
+    }).collect()
+}
+
+fn new_paper_links(papers: &[I::NewPaper], papers_map: &HashMap<String, i32>) -> Vec<NewPaperLink> {
+    papers.iter().flat_map(|p| {
+        let paper_id = papers_map.get(&p.url).unwrap().to_owned();
+        p.links.iter().map(move |l| NewPaperLink {
+            paper_id,
+            kind: l.kind.as_str().to_string(),
+            url: l.url.to_owned(),
+        })
+    }).collect()
+}
+
+fn new_paper_counts(papers: &[I::NewPaper], papers_map: &HashMap<String, i32>) -> Vec<NewPaperCount> {
+    papers.iter()
+        .filter(|p| !p.counts.is_empty())
+        .map(|p| NewPaperCount {
+            paper_id: papers_map.get(&p.url).unwrap().to_owned(),
+            pages: p.counts.pages.map(|n| n as i32),
+            figures: p.counts.figures.map(|n| n as i32),
+            tables: p.counts.tables.map(|n| n as i32),
+        }).collect()
+}
+
+fn new_paper_venues(papers: &[I::NewPaper], papers_map: &HashMap<String, i32>) -> Vec<NewPaperVenue> {
+    papers.iter().filter_map(|p| {
+        let venue = p.venue.as_ref()?;
+        Some(NewPaperVenue {
+            paper_id: papers_map.get(&p.url).unwrap().to_owned(),
+            name: venue.name.to_owned(),
+            year: venue.year,
+            track: venue.track.as_ref().map(|t| t.as_str().to_string()),
+            status: venue.status.as_str().to_string(),
+        })
+    }).collect()
+}
+
+// バージョンの分からない論文（OAI-PMHのarXiv形式など）は記録しない
+fn new_paper_versions(papers: &[I::NewPaper], ids_map: &HashMap<String, i32>) -> Vec<NewPaperVersion> {
+    papers.iter().filter_map(|p| {
+        let arxiv_id = p.arxiv_id.as_ref()?;
+        Some(NewPaperVersion {
+            paper_id: ids_map.get(&arxiv_id.id)?.to_owned(),
+            version: arxiv_id.version? as i32,
+            title: p.title.to_owned(),
+            summary: p.summary.to_owned(),
+            comment: p.comment.to_owned(),
+            updated: p.updated,
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+pub(crate) fn test_paper(url: &str, title: &str, authors: &[&str], category: &str) -> I::NewPaper {
+    use crate::domain::arxiv_api::{Affiliation, Paper as ApiPaper};
+    let updated = chrono::NaiveDate::from_ymd(2021, 4, 1).and_hms(0, 0, 0);
+    I::NewPaper::from(ApiPaper {
+        title: title.to_string(),
+        url: url.to_string(),
+        pdf_url: url.replace("/abs/", "/pdf/"),
+        authors: authors.iter().map(|a| a.to_string()).collect(),
+        category: category.to_string(),
+        categories: vec![category.to_string(), "stat.ML".to_string()],
+        summary: "Code is available at https://github.com/example/repo".to_string(),
+        comment: "Accepted to CVPR 2021. 8 pages, 3 figures".to_string(),
+        is_accepted: true,
+        journal_ref: None,
+        doi: None,
+        affiliations: vec![Affiliation { author: authors[0].to_string(), name: "University of Tokyo".to_string() }],
+        classes: vec!["I.4.8".to_string()],
+        updated,
+        published: updated,
+    })
+}
+
+#[test]
+fn test_save_and_find() {
+    use super::database::test_connections;
+    use I::ArxivPaperRepositoryTrait;
+    for conn in test_connections() {
+        let repository = ArxivPaperRepository::new(conn.clone());
+        let v1 = test_paper("http://arxiv.org/abs/2104.90001v1", "Old Title", &["Taro Yamada", "Hanako Suzuki"], "test.AP");
+        let other = test_paper("http://arxiv.org/abs/2104.90002v1", "Other", &["Tarō Yamáda"], "test.AP");
+        assert_eq!(repository.save(&vec![v1.clone(), other]).unwrap(), 2);
+        // 保存済みの論文は無視する
+        assert_eq!(repository.save(&vec![v1]).unwrap(), 0);
+        // 保存済みの表記揺れもフォローしている著者として扱う
+        assert_eq!(repository.find_author_names(&["Yamada, T.".to_string()]).unwrap(), vec!["Taro Yamada", "Tarō Yamáda"]);
+
+        let mut v2 = test_paper("http://arxiv.org/abs/2104.90001v2", "New Title", &["Jiro Sato"], "test.AP");
+        v2.updated += chrono::Duration::days(1);
+        v2.venue = Some(Venue { name: "CVPR".to_string(), year: Some(2021), track: None, status: AcceptanceStatus::Accepted });
+        assert_eq!(repository.save(&vec![v2]).unwrap(), 1);
+
+        let papers = repository.find_by_urls(&vec!["http://arxiv.org/abs/2104.90001v2".to_string()]).unwrap();
+        assert_eq!(papers.len(), 1, "{}", conn.backend());
+        let paper = repository.find_by_id(papers[0].id.clone()).unwrap().unwrap();
+        assert_eq!(paper.title, "New Title");
+        assert_eq!(paper.authors.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), vec!["Jiro Sato"]);
+        assert_eq!(paper.authors[0].affiliations, vec!["University of Tokyo"]);
+        assert_eq!(paper.categories.len(), 2);
+        assert_eq!(paper.classes, vec!["I.4.8"]);
+        assert_eq!(paper.versions.iter().map(|v| v.version).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(paper.previous_version().unwrap().title, "Old Title");
+        assert_eq!(paper.venue.unwrap().year, Some(2021));
+        assert_eq!(paper.links.len(), 1);
+        assert_eq!(paper.counts.pages, Some(8));
+
+        // 古いバージョンのarXiv IDでも保存済みの行が見つかる
+        let papers = repository.find_by_arxiv_ids(&["2104.90001".to_string()]).unwrap();
+        assert_eq!(papers.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), vec!["New Title"]);
+        let papers = repository.find_by_ids(&[papers[0].id.clone()]).unwrap();
+        assert_eq!(papers[0].authors[0].affiliations, vec!["University of Tokyo"]);
+    }
+}
+
+#[test]
+fn test_save_with_legacy_rows() {
+    use super::database::test_connections;
+    use I::ArxivPaperRepositoryTrait;
+    for conn in test_connections() {
+        let repository = ArxivPaperRepository::new(conn.clone());
+        let v2 = test_paper("http://arxiv.org/abs/2104.90003v2", "New Title", &["Taro Yamada"], "test.AP");
+        assert_eq!(repository.save(&vec![v2.clone()]).unwrap(), 1);
+        // 以前はバージョンごとに別の行として保存していたので、古いバージョンの行が後から追加されている場合がある
+        let category_id: i32 = with_connection!(conn, |c| categories::table
+            .filter(categories::name.eq("test.AP"))
+            .select(categories::id)
+            .first(c)).unwrap();
+        let mut v1 = test_paper("http://arxiv.org/abs/2104.90003v1", "Old Title", &["Taro Yamada"], "test.AP");
+        v1.updated -= chrono::Duration::days(1);
+        let legacy = new_papers(&vec![v1], &vec![("test.AP".to_string(), category_id)].into_iter().collect());
+        with_connection!(conn, |c| diesel::insert_into(papers::table).values(&legacy).execute(c)).unwrap();
+
+        assert_eq!(repository.save(&vec![v2]).unwrap(), 0, "{}", conn.backend());
+        let v3 = test_paper("http://arxiv.org/abs/2104.90003v3", "Newest Title", &["Taro Yamada"], "test.AP");
+        assert_eq!(repository.save(&vec![v3]).unwrap(), 1);
+        let papers = repository.find_by_urls(&vec!["http://arxiv.org/abs/2104.90003v3".to_string()]).unwrap();
+        assert_eq!(papers[0].title, "Newest Title");
+    }
+}
+
+#[test]
+fn test_save_versionless_record() {
+    use super::database::test_connections;
+    use I::ArxivPaperRepositoryTrait;
+    for conn in test_connections() {
+        let repository = ArxivPaperRepository::new(conn.clone());
+        let v2 = test_paper("http://arxiv.org/abs/2104.90004v2", "Versioned Title", &["Taro Yamada"], "test.VL");
+        assert_eq!(repository.save(&vec![v2]).unwrap(), 1);
+        // OAIのレコードはバージョンが分からないので、更新日時が新しくても置き換えない
+        let mut oai = test_paper("http://arxiv.org/abs/2104.90004", "OAI Title", &["Taro Yamada"], "test.VL");
+        oai.updated += chrono::Duration::days(1);
+        oai.journal_ref = Some("CVPR 2021".to_string());
+        assert_eq!(repository.save(&vec![oai]).unwrap(), 0, "{}", conn.backend());
+        let papers = repository.find_by_urls(&vec!["http://arxiv.org/abs/2104.90004v2".to_string()]).unwrap();
+        assert_eq!(papers[0].title, "Versioned Title");
+        // 欠けている書誌情報だけ補う
+        assert_eq!(papers[0].journal_ref.as_deref(), Some("CVPR 2021"));
+    }
+}
+
+#[test]
+fn test_download() {
+    use super::database::test_connections;
+    use super::pdf_library::test_library;
+    use crate::mock_server::{MockResponse, MockServer};
+    use I::ArxivPaperRepositoryTrait;
+    for conn in test_connections() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "%PDF-1.4 v1"),
+            MockResponse::new(200, "%PDF-1.4 other"),
+            MockResponse::new(200, "%PDF-1.4 v2"),
+        ]);
+        let library = test_library("download", &server.url());
+        let repository = ArxivPaperRepository::with_library(conn.clone(), library.clone());
+        let urls = vec!["http://arxiv.org/abs/2104.90201v1".to_string(), "http://arxiv.org/abs/2104.90202v3".to_string()];
+        repository.save(&vec![
+            test_paper(&urls[0], "Title", &["Shiro Ito"], "test.DL"),
+            test_paper(&urls[1], "Other", &["Shiro Ito"], "test.DL"),
+        ]).unwrap();
+        let papers = repository.find_by_urls(&urls).unwrap();
+        let files = repository.download(&papers).unwrap();
+        assert_eq!(files.len(), 2, "{}", conn.backend());
+        assert_eq!(files.iter().map(|f| f.version).collect::<Vec<u32>>(), vec![1, 3]);
+        assert_eq!(files[0].size, 11);
+        assert!(library.exists(&files[0].path));
+        assert_eq!(repository.find_files(&[papers[0].id.clone()]).unwrap(), vec![files[0].clone()]);
+
+        // ダウンロード済みのバージョンはダウンロードしない
+        assert!(repository.download(&papers).unwrap().is_empty());
+        // ファイルが消えていればダウンロードし直す
+        std::fs::remove_file(library.dir().join(&files[1].path)).unwrap();
+        assert_eq!(repository.download(&papers).unwrap().len(), 1);
+        // 新しいバージョンはダウンロードする
+        let mut v2 = test_paper("http://arxiv.org/abs/2104.90201v2", "Title", &["Shiro Ito"], "test.DL");
+        v2.updated += chrono::Duration::days(1);
+        repository.save(&vec![v2]).unwrap();
+        let papers = repository.find_by_urls(&vec!["http://arxiv.org/abs/2104.90201v2".to_string()]).unwrap();
+        assert_eq!(repository.download(&papers).unwrap()[0].version, 2);
+        assert_eq!(repository.find_files(&[papers[0].id.clone()]).unwrap().len(), 2);
+        assert_eq!(server.requests(), vec!["/2104.90201v1", "/2104.90202v3", "/2104.90202v3", "/2104.90201v2"]);
+        std::fs::remove_dir_all(library.dir()).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use super::arxiv_api::{author_separator, split_authors};
+use super::recording;
+use super::retry::{get_text, RetryError, RetryPolicy};
+use crate::domain::arxiv_api as I;
+use chrono::DateTime;
+use quick_xml::de::from_str;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_RSS_URL: &str = "https://rss.arxiv.org/rss";
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Item {
+    title: String,
+    description: String,
+    guid: String,
+    #[serde(default)]
+    category: Vec<String>,
+    #[serde(rename = "pubDate")]
+    pub_date: String,
+    announce_type: String,
+    creator: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Channel {
+    #[serde(default)]
+    item: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Rss {
+    channel: Channel,
+}
+
+// 新着(new)、他カテゴリからのクロスリスト(cross)、改訂版(replace, replace-cross)の区別
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnounceType {
+    New,
+    Cross,
+    Replace,
+    ReplaceCross,
+}
+
+impl AnnounceType {
+    pub fn parse(announce_type: &str) -> anyhow::Result<Self> {
+        match announce_type {
+            "new" => Ok(AnnounceType::New),
+            "cross" => Ok(AnnounceType::Cross),
+            "replace" => Ok(AnnounceType::Replace),
+            "replace-cross" => Ok(AnnounceType::ReplaceCross),
+            _ => anyhow::bail!(
+                "Unknown announce type `{}` (use one of new, cross, replace, replace-cross)",
+                announce_type
+            ),
+        }
+    }
+}
+
+// arXivのカテゴリごとの日次RSSから、その日に公開された論文を取得する
+#[derive(Debug, Clone)]
+pub struct ArxivRssSource {
+    rss_url: String,
+    categories: Vec<String>,
+    announce_types: Option<Vec<AnnounceType>>,
+    retry_policy: RetryPolicy,
+    record_dir: Option<PathBuf>,
+    replay_dir: Option<PathBuf>,
+}
+
+impl ArxivRssSource {
+    pub fn new(categories: &[String]) -> Self {
+        Self {
+            rss_url: DEFAULT_RSS_URL.to_string(),
+            categories: categories.to_vec(),
+            announce_types: None,
+            retry_policy: RetryPolicy::default(),
+            record_dir: None,
+            replay_dir: None,
+        }
+    }
+
+    pub fn rss_url(&mut self, rss_url: &str) -> &mut Self {
+        self.rss_url = rss_url.to_string();
+        self
+    }
+
+    // 指定しない場合は全ての種類を取得する
+    pub fn announce_types(&mut self, announce_types: &[AnnounceType]) -> &mut Self {
+        self.announce_types = Some(announce_types.to_vec());
+        self
+    }
+
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn record(&mut self, dir: &Path) -> &mut Self {
+        self.record_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    pub fn replay(&mut self, dir: &Path) -> &mut Self {
+        self.replay_dir = Some(dir.to_path_buf());
+        self
+    }
+
+    fn generate_rss_url(&self) -> String {
+        // 複数のカテゴリは+でつなぐと一つのフィードにまとめて取得できる
+        format!("{}/{}", self.rss_url, self.categories.join("+"))
+    }
+
+    fn fetch_xml(&self, url: &str) -> Result<String, RetryError> {
+        println!("{}", url);
+        if let Some(dir) = &self.replay_dir {
+            return recording::replay(dir, url).map_err(RetryError::Fatal);
+        }
+        let xml = get_text(url)?;
+        if let Some(dir) = &self.record_dir {
+            recording::record(dir, url, &xml).map_err(RetryError::Fatal)?;
+        }
+        Ok(xml)
+    }
+
+    fn to_rss(&self, xml: &str) -> anyhow::Result<Rss> {
+        Ok(from_str(xml)?)
+    }
+
+    // descriptionは "arXiv:2610.01234v1 Announce Type: new \nAbstract: ..." の形式
+    fn description_regex() -> Regex {
+        Regex::new(r"(?s)^\s*arXiv:(\S+)\s+Announce Type:\s*\S+\s*(?:Abstract:)?(.*)$").unwrap()
+    }
+
+    fn convert(&self, item: &Item, description: &Regex, author_separator: &Regex) -> anyhow::Result<I::Paper> {
+        let caps = description.captures(&item.description)
+            .ok_or_else(|| anyhow::anyhow!("Unexpected description in {}", item.guid))?;
+        let id = &caps[1];
+        let summary = caps[2].split_whitespace().collect::<Vec<&str>>().join(" ");
+        let date = DateTime::parse_from_rfc2822(item.pub_date.trim())?.naive_utc();
+        Ok(I::Paper {
+            title: item.title.split_whitespace().collect::<Vec<&str>>().join(" "),
+            url: format!("http://arxiv.org/abs/{}", id),
+            pdf_url: format!("http://arxiv.org/pdf/{}", id),
+            authors: split_authors(item.creator.as_deref().unwrap_or(""), author_separator),
+            // 先頭がメインカテゴリ
+            category: item.category.first().cloned().unwrap_or_default(),
+            categories: item.category.clone(),
+            summary,
+            // RSSにはコメントが含まれない
+            comment: "".to_string(),
+            is_accepted: false,
+            journal_ref: None,
+            doi: None,
+            affiliations: vec![],
+            classes: vec![],
+            // RSSには公開日しか含まれないため、投稿日時も公開日とする
+            updated: date,
+            published: date,
+        })
+    }
+
+    fn is_wanted(&self, item: &Item) -> bool {
+        // 知らない種類が増えてもフィード全体は失敗させず、その論文だけ飛ばす
+        let announce_type = match AnnounceType::parse(item.announce_type.trim()) {
+            Ok(announce_type) => announce_type,
+            Err(e) => {
+                eprintln!("Skipped {}: {}", item.guid.trim(), e);
+                return false;
+            }
+        };
+        match &self.announce_types {
+            Some(types) => types.contains(&announce_type),
+            None => true,
+        }
+    }
+}
+
+impl I::ArxivAPITrait for ArxivRssSource {
+    fn query(&self) -> anyhow::Result<Vec<I::Paper>> {
+        let url = self.generate_rss_url();
+        let rss = self.retry_policy.run(|| {
+            let xml = self.fetch_xml(&url)?;
+            self.to_rss(&xml).map_err(RetryError::Fatal)
+        })?;
+        let mut papers = vec![];
+        let description = Self::description_regex();
+        let author_separator = author_separator();
+        for item in &rss.channel.item {
+            if self.is_wanted(item) {
+                papers.push(self.convert(item, &description, &author_separator)?);
+            }
+        }
+        Ok(papers)
+    }
+}
+
+#[test]
+fn test_rss_query() {
+    use crate::domain::arxiv_api::ArxivAPITrait;
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(200, include_str!("../../tests/fixtures/rss/cs.CV.xml")),
+    ]);
+    let mut rss = ArxivRssSource::new(&["cs.CV".to_string(), "eess.IV".to_string()]);
+    rss.rss_url(&server.url());
+    let papers = rss.query().unwrap();
+    assert_eq!(papers.len(), 3);
+    assert_eq!(papers[0].url, "http://arxiv.org/abs/2610.01234v1");
+    assert_eq!(papers[0].pdf_url, "http://arxiv.org/pdf/2610.01234v1");
+    assert_eq!(papers[0].authors, vec!["Taro Yamada", "Jane Doe"]);
+    assert_eq!(papers[0].summary, "We recognize faces in the wild.");
+    assert_eq!(papers[1].category, "cs.LG");
+    assert!(server.requests()[0].ends_with("/cs.CV+eess.IV"));
+
+    let server = MockServer::start(vec![
+        MockResponse::new(200, include_str!("../../tests/fixtures/rss/cs.CV.xml")),
+    ]);
+    let papers = rss.rss_url(&server.url())
+        .announce_types(&[AnnounceType::New, AnnounceType::Cross])
+        .query()
+        .unwrap();
+    assert_eq!(papers.len(), 2);
+    assert_eq!(papers[1].title, "Learning to Segment");
+
+    // 知らない種類の論文だけ飛ばす
+    let xml = include_str!("../../tests/fixtures/rss/cs.CV.xml").replace(">replace<", ">withdrawn<");
+    let server = MockServer::start(vec![MockResponse::new(200, &xml)]);
+    let papers = ArxivRssSource::new(&["cs.CV".to_string()]).rss_url(&server.url()).query().unwrap();
+    assert_eq!(papers.len(), 2);
+}
@@ -0,0 +1,390 @@
+use chrono::NaiveDateTime;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::arxiv_api::is_accepted;
+use super::arxiv_paper::latest_versions;
+use super::slack_api::SlackAPI;
+use crate::domain::arxiv_id::ArxivId;
+use crate::domain::author_name::find_followed;
+use crate::domain::arxiv_paper as I;
+use crate::domain::oai_harvest as O;
+use crate::domain::slack_api::SlackAPITrait;
+use crate::domain::slack_notification as N;
+use crate::domain::subscription_state as S;
+
+struct Notification {
+    paper_id: I::PaperId,
+    slack_url: String,
+    updated: NaiveDateTime,
+    send: bool,
+}
+
+// DBの代わりにメモリ上に保存するテーブル。テストや--ephemeralでの実行に使い、プロセスが終わると消える
+#[derive(Default)]
+pub struct Tables {
+    // 論文のidは添字+1
+    papers: Vec<I::Paper>,
+    authors: Vec<String>,
+    categories: Vec<String>,
+    notifications: Vec<Notification>,
+    subscription_state: HashMap<String, NaiveDateTime>,
+    subscription_papers: Vec<(String, I::PaperId)>,
+    oai_harvests: HashMap<String, Option<String>>,
+}
+
+// DbConnectionと同じく、一回の実行の間は全てのリポジトリで共有する
+pub type InMemoryStore = Rc<RefCell<Tables>>;
+
+impl Tables {
+    fn author_id(&mut self, name: &str) -> i32 {
+        match self.authors.iter().position(|a| a == name) {
+            Some(i) => i as i32 + 1,
+            None => {
+                self.authors.push(name.to_string());
+                self.authors.len() as i32
+            }
+        }
+    }
+
+    fn category(&mut self, name: &str) -> I::Category {
+        let id = match self.categories.iter().position(|c| c == name) {
+            Some(i) => i as i32 + 1,
+            None => {
+                self.categories.push(name.to_string());
+                self.categories.len() as i32
+            }
+        };
+        I::Category { id, name: name.to_string() }
+    }
+
+    // DBと同じく、バージョンを除いたarXiv IDが同じ論文を同一視する
+    fn position(&self, paper: &I::NewPaper) -> Option<usize> {
+        match &paper.arxiv_id {
+            Some(id) => self.papers.iter()
+                .position(|p| p.arxiv_id.as_ref().map(|a| &a.id) == Some(&id.id)),
+            None => self.papers.iter().position(|p| p.url == paper.url),
+        }
+    }
+
+    fn paper(&mut self, id: i32, paper: &I::NewPaper, versions: Vec<I::PaperVersion>) -> I::Paper {
+        let mut categories: Vec<I::Category> = std::iter::once(&paper.category).chain(paper.categories.iter())
+            .map(|c| self.category(&c.name))
+            .collect();
+        categories.sort_by_key(|c| c.id);
+        categories.dedup_by_key(|c| c.id);
+        I::Paper {
+            id: I::PaperId(id),
+            arxiv_id: ArxivId::parse(&paper.url).ok(),
+            title: paper.title.clone(),
+            url: paper.url.clone(),
+            pdf_url: paper.pdf_url.clone(),
+            authors: paper.authors.iter().map(|a| I::Author {
+                id: self.author_id(&a.name),
+                name: a.name.clone(),
+                affiliations: a.affiliations.clone(),
+            }).collect(),
+            category: self.category(&paper.category.name),
+            categories,
+            summary: paper.summary.clone(),
+            comment: paper.comment.clone(),
+            is_accepted: paper.is_accepted,
+            journal_ref: paper.journal_ref.clone(),
+            doi: paper.doi.clone(),
+            classes: paper.classes.clone(),
+            venue: paper.venue.clone(),
+            links: paper.links.clone(),
+            counts: paper.counts.clone(),
+            versions,
+            updated: paper.updated,
+            published: paper.published,
+        }
+    }
+}
+
+fn version(paper: &I::NewPaper) -> Option<u32> {
+    paper.arxiv_id.as_ref().and_then(|id| id.version)
+}
+
+// バージョンが分かる場合はバージョンで、分からない場合は更新日時で比べる
+// バージョンの分からないレコード(OAIなど)では、バージョンの分かる論文を置き換えない
+fn is_newer(paper: &I::NewPaper, stored: &I::Paper) -> bool {
+    match (version(paper), stored.arxiv_id.as_ref().and_then(|id| id.version)) {
+        (Some(new), Some(old)) => new > old,
+        (None, Some(_)) => false,
+        _ => paper.updated > stored.updated,
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryArxivPaperRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryArxivPaperRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+impl I::ArxivPaperRepositoryTrait for InMemoryArxivPaperRepository {
+    fn find_by_id(&self, id: I::PaperId) -> anyhow::Result<Option<I::Paper>> {
+        Ok(self.store.borrow().papers.iter().find(|p| p.id == id).cloned())
+    }
+
+    fn find_by_urls(&self, urls: &Vec<String>) -> anyhow::Result<Vec<I::Paper>> {
+        Ok(self.store.borrow().papers.iter().filter(|p| urls.contains(&p.url)).cloned().collect())
+    }
+
+    fn find_by_ids(&self, ids: &[I::PaperId]) -> anyhow::Result<Vec<I::Paper>> {
+        Ok(self.store.borrow().papers.iter().filter(|p| ids.contains(&p.id)).cloned().collect())
+    }
+
+    fn find_by_arxiv_ids(&self, arxiv_ids: &[String]) -> anyhow::Result<Vec<I::Paper>> {
+        Ok(self.store.borrow().papers.iter()
+            .filter(|p| matches!(&p.arxiv_id, Some(id) if arxiv_ids.contains(&id.id)))
+            .cloned()
+            .collect())
+    }
+
+    fn save(&self, all_versions: &Vec<I::NewPaper>) -> anyhow::Result<usize> {
+        let mut tables = self.store.borrow_mut();
+        let mut n = 0;
+        for p in latest_versions(all_versions) {
+            match tables.position(&p) {
+                None => {
+                    let id = tables.papers.len() as i32 + 1;
+                    let paper = tables.paper(id, &p, vec![]);
+                    tables.papers.push(paper);
+                    n += 1;
+                }
+                // 新しいバージョンの場合だけ上書きする
+                Some(i) if is_newer(&p, &tables.papers[i]) => {
+                    let versions = std::mem::take(&mut tables.papers[i].versions);
+                    let paper = tables.paper(i as i32 + 1, &p, versions);
+                    tables.papers[i] = paper;
+                    n += 1;
+                }
+                // 上書きしない場合も、欠けているjournal_refやDOIは補う
+                Some(i) => {
+                    let stored = &mut tables.papers[i];
+                    if stored.journal_ref.is_none() {
+                        stored.journal_ref = p.journal_ref.clone();
+                    }
+                    if stored.doi.is_none() {
+                        stored.doi = p.doi.clone();
+                    }
+                }
+            }
+        }
+        // 古いバージョンしか取得できなかった場合も履歴としては残す
+        for p in all_versions {
+            let (i, v) = match (tables.position(p), version(p)) {
+                (Some(i), Some(v)) => (i, v),
+                _ => continue,
+            };
+            let versions = &mut tables.papers[i].versions;
+            if versions.iter().all(|stored| stored.version != v) {
+                versions.push(I::PaperVersion {
+                    version: v,
+                    title: p.title.clone(),
+                    summary: p.summary.clone(),
+                    comment: p.comment.clone(),
+                    is_accepted: is_accepted(&p.comment),
+                    updated: p.updated,
+                });
+                versions.sort_by_key(|v| v.version);
+            }
+        }
+        Ok(n)
+    }
+
+    // メモリ上の論文はPDFをダウンロードしない
+    fn find_files(&self, _ids: &[I::PaperId]) -> anyhow::Result<Vec<I::PaperFile>> {
+        Ok(vec![])
+    }
+
+    fn find_author_names(&self, follow_authors: &[String]) -> anyhow::Result<Vec<String>> {
+        let mut names: Vec<String> = self.store.borrow().papers.iter()
+            .flat_map(|p| p.authors.iter().map(|a| a.name.clone()))
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(find_followed(&names, follow_authors).into_iter().cloned().collect())
+    }
+
+    fn download(&self, _papers: &Vec<I::Paper>) -> anyhow::Result<Vec<I::PaperFile>> {
+        Ok(vec![])
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemorySlackNotificationRepository {
+    store: InMemoryStore,
+}
+
+impl InMemorySlackNotificationRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+impl N::SlackNotificationRepositoryTrait for InMemorySlackNotificationRepository {
+    fn save(&self, notifications: &Vec<N::NewSlackNotification>) -> anyhow::Result<usize> {
+        let mut tables = self.store.borrow_mut();
+        let mut n = 0;
+        for notification in notifications {
+            // 同じ論文の同じ更新日時の通知は一度だけ（DBのUNIQUE制約と同じ）
+            let exists = tables.notifications.iter().any(|stored| stored.slack_url == notification.slack_url
+                && stored.paper_id == notification.paper_id
+                && stored.updated == notification.updated);
+            if !exists {
+                tables.notifications.push(Notification {
+                    paper_id: notification.paper_id.clone(),
+                    slack_url: notification.slack_url.clone(),
+                    updated: notification.updated,
+                    send: false,
+                });
+                n += 1;
+            }
+        }
+        Ok(n)
+    }
+
+    fn find_not_send(&self, slack_url: &str) -> anyhow::Result<Vec<I::Paper>> {
+        let tables = self.store.borrow();
+        Ok(tables.notifications.iter()
+            .filter(|n| n.slack_url == slack_url && !n.send)
+            .filter_map(|n| tables.papers.iter().find(|p| p.id == n.paper_id).cloned())
+            .collect())
+    }
+
+    fn find_notified(&self, slack_url: &str, paper_ids: &[I::PaperId]) -> anyhow::Result<Vec<I::PaperId>> {
+        let tables = self.store.borrow();
+        let mut notified: Vec<I::PaperId> = vec![];
+        for n in tables.notifications.iter().filter(|n| n.slack_url == slack_url && paper_ids.contains(&n.paper_id)) {
+            if !notified.contains(&n.paper_id) {
+                notified.push(n.paper_id.clone());
+            }
+        }
+        Ok(notified)
+    }
+
+    fn mark_as_send(&self, slack_url: &str, paper_id: &I::PaperId) -> anyhow::Result<usize> {
+        let mut tables = self.store.borrow_mut();
+        let mut n = 0;
+        for notification in tables.notifications.iter_mut()
+            .filter(|n| n.slack_url == slack_url && &n.paper_id == paper_id) {
+            notification.send = true;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    fn delete(&self, slack_url: &str, paper_id: &I::PaperId) -> anyhow::Result<()> {
+        self.store.borrow_mut().notifications
+            .retain(|n| !(n.slack_url == slack_url && &n.paper_id == paper_id));
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemorySubscriptionStateRepository {
+    store: InMemoryStore,
+}
+
+impl InMemorySubscriptionStateRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+impl S::SubscriptionStateRepositoryTrait for InMemorySubscriptionStateRepository {
+    fn find(&self, subscription: &str) -> anyhow::Result<Option<S::SubscriptionState>> {
+        Ok(self.store.borrow().subscription_state.get(subscription).map(|last_updated| S::SubscriptionState {
+            subscription: subscription.to_string(),
+            last_updated: *last_updated,
+        }))
+    }
+
+    fn save(&self, state: &S::SubscriptionState) -> anyhow::Result<usize> {
+        self.store.borrow_mut().subscription_state.insert(state.subscription.clone(), state.last_updated);
+        Ok(1)
+    }
+
+    fn add_papers(&self, subscription: &str, paper_ids: &[I::PaperId]) -> anyhow::Result<usize> {
+        let mut tables = self.store.borrow_mut();
+        let mut n = 0;
+        for id in paper_ids {
+            let exists = tables.subscription_papers.iter().any(|(s, stored)| s == subscription && stored == id);
+            if !exists {
+                tables.subscription_papers.push((subscription.to_string(), id.clone()));
+                n += 1;
+            }
+        }
+        Ok(n)
+    }
+
+    fn find_paper_ids(&self, subscription: &str) -> anyhow::Result<Vec<I::PaperId>> {
+        Ok(self.store.borrow().subscription_papers.iter()
+            .filter(|(s, _)| s == subscription)
+            .map(|(_, id)| id.clone())
+            .collect())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryOaiHarvestRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryOaiHarvestRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+impl O::OaiHarvestRepositoryTrait for InMemoryOaiHarvestRepository {
+    fn find(&self, harvest: &str) -> anyhow::Result<Option<O::OaiHarvest>> {
+        Ok(self.store.borrow().oai_harvests.get(harvest).map(|token| O::OaiHarvest {
+            harvest: harvest.to_string(),
+            resumption_token: token.clone(),
+        }))
+    }
+
+    fn save(&self, harvest: &O::OaiHarvest) -> anyhow::Result<usize> {
+        self.store.borrow_mut().oai_harvests.insert(harvest.harvest.clone(), harvest.resumption_token.clone());
+        Ok(1)
+    }
+}
+
+// slackに送らずに、送ろうとしたメッセージを記録する。メッセージの組み立てはSlackAPIと同じ
+pub struct InMemorySlackAPI {
+    slack_api: SlackAPI,
+    messages: RefCell<Vec<String>>,
+}
+
+impl InMemorySlackAPI {
+    pub fn new(url: &str) -> Self {
+        InMemorySlackAPI {
+            slack_api: SlackAPI::new(url),
+            messages: RefCell::new(vec![]),
+        }
+    }
+
+    // sendされたメッセージを送った順に返す
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.borrow().clone()
+    }
+}
+
+impl SlackAPITrait for InMemorySlackAPI {
+    fn send(&self, message: &str) {
+        self.messages.borrow_mut().push(message.to_string());
+    }
+
+    fn build_messages(&self, papers: &Vec<I::Paper>, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> Vec<(I::Paper, String)> {
+        self.slack_api.build_messages(papers, keywords, follow_authors)
+    }
+}
@@ -0,0 +1,52 @@
+use diesel::prelude::*;
+
+use super::database::DbConnection;
+use crate::db::models::OaiHarvest;
+use crate::db::schema::*;
+use crate::domain::oai_harvest as I;
+
+#[derive(Insertable, Debug)]
+#[table_name = "oai_harvests"]
+struct NewOaiHarvest {
+    pub harvest: String,
+    pub resumption_token: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct OaiHarvestRepository {
+    conn: DbConnection,
+}
+
+impl OaiHarvestRepository {
+    pub fn new(conn: DbConnection) -> Self {
+        Self { conn }
+    }
+}
+
+impl I::OaiHarvestRepositoryTrait for OaiHarvestRepository {
+    fn find(&self, harvest: &str) -> anyhow::Result<Option<I::OaiHarvest>> {
+        let state: Option<OaiHarvest> = with_connection!(self.conn, |conn| oai_harvests::table
+            .find(harvest)
+            .first(conn)
+            .optional())?;
+        Ok(state.map(|s| I::OaiHarvest {
+            harvest: s.harvest,
+            resumption_token: s.resumption_token,
+        }))
+    }
+
+    fn save(&self, harvest: &I::OaiHarvest) -> anyhow::Result<usize> {
+        // REPLACE INTOはSQLiteにしかないので、消してから入れ直す（updatedもDEFAULTで更新される）
+        let n = with_connection!(self.conn, |conn| conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(oai_harvests::table.find(&harvest.harvest))
+                .execute(conn)?;
+            diesel::insert_into(oai_harvests::table)
+                .values(&NewOaiHarvest {
+                    harvest: harvest.harvest.clone(),
+                    resumption_token: harvest.resumption_token.clone(),
+                })
+                .execute(conn)
+        }))?;
+        Ok(n)
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::{thread, time};
+
+use super::retry::{get_bytes, RetryError, RetryPolicy};
+
+const DEFAULT_LIBRARY: &str = "library";
+const DEFAULT_PDF_URL: &str = "https://arxiv.org/pdf";
+// arXiv APIと同じく、連続してダウンロードする場合は3秒あける
+const PDF_REQUEST_INTERVAL_MILLIS: u64 = 3000;
+
+// setting.tomlと同じく、実行するディレクトリによらずリポジトリの下に置く
+// ビルドしたリポジトリがない環境では、実行するディレクトリからの相対パス
+pub fn default_library() -> PathBuf {
+    let repository = Path::new(env!("CARGO_MANIFEST_DIR"));
+    match repository.is_dir() {
+        true => repository.join(DEFAULT_LIBRARY),
+        false => PathBuf::from(DEFAULT_LIBRARY),
+    }
+}
+
+// ライブラリに保存したPDF。pathはライブラリのディレクトリからの相対パス
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredPdf {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+// ダウンロードしたPDFを置くディレクトリ。ファイル名はSHA-256で、同じ内容のPDFは一つのファイルになる
+#[derive(Clone)]
+pub struct PdfLibrary {
+    dir: PathBuf,
+    pdf_url: String,
+    retry_policy: RetryPolicy,
+    request_interval: time::Duration,
+    last_request: Cell<Option<time::Instant>>,
+}
+
+impl PdfLibrary {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            pdf_url: DEFAULT_PDF_URL.to_string(),
+            retry_policy: RetryPolicy::default(),
+            request_interval: time::Duration::from_millis(PDF_REQUEST_INTERVAL_MILLIS),
+            last_request: Cell::new(None),
+        }
+    }
+
+    pub fn pdf_url(&mut self, pdf_url: &str) -> &mut Self {
+        self.pdf_url = pdf_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn request_interval(&mut self, request_interval: time::Duration) -> &mut Self {
+        self.request_interval = request_interval;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.dir.join(path).is_file()
+    }
+
+    // バージョンを除いたarXiv IDとバージョンを指定してPDFをダウンロードし、ライブラリに保存する
+    pub fn fetch(&self, id: &str, version: u32) -> anyhow::Result<StoredPdf> {
+        if let Some(last) = self.last_request.get() {
+            if let Some(wait) = self.request_interval.checked_sub(last.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        let url = format!("{}/{}v{}", self.pdf_url, id, version);
+        println!("{}", &url);
+        let body = self.retry_policy.run(|| {
+            self.last_request.set(Some(time::Instant::now()));
+            let body = get_bytes(&url)?;
+            // PDFの生成が終わっていない場合などはHTMLが返ってくる
+            match body.starts_with(b"%PDF") {
+                true => Ok(body),
+                false => Err(RetryError::transient(anyhow::anyhow!("{} did not return a PDF", url))),
+            }
+        })?;
+        self.store(&body)
+    }
+
+    fn store(&self, body: &[u8]) -> anyhow::Result<StoredPdf> {
+        let sha256 = format!("{:x}", Sha256::digest(body));
+        let path = format!("{}/{}.pdf", &sha256[..2], &sha256);
+        let file = self.dir.join(&path);
+        if !file.is_file() {
+            let dir = file.parent().unwrap();
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Could not create directory {}", dir.display()))?;
+            // 中断しても書きかけのファイルが残らないように、一時ファイルに書いてから移す
+            let part = file.with_extension("pdf.part");
+            fs::write(&part, body).with_context(|| format!("Could not write {}", part.display()))?;
+            fs::rename(&part, &file).with_context(|| format!("Could not write {}", file.display()))?;
+        }
+        Ok(StoredPdf {
+            path,
+            size: body.len() as u64,
+            sha256,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_library(name: &str, pdf_url: &str) -> PdfLibrary {
+    let dir = std::env::temp_dir().join(format!("arxiv-bot-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let mut library = PdfLibrary::new(&dir);
+    library.pdf_url(pdf_url)
+        .request_interval(time::Duration::from_millis(0))
+        .retry_policy(RetryPolicy {
+            max_attempts: 2,
+            base_delay: time::Duration::from_millis(0),
+            max_delay: time::Duration::from_millis(0),
+            jitter: time::Duration::from_millis(0),
+        });
+    library
+}
+
+#[test]
+fn test_fetch() {
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(200, "<html>PDF is being generated</html>"),
+        MockResponse::new(200, "%PDF-1.4 face parsing"),
+        MockResponse::new(200, "%PDF-1.4 face parsing"),
+        MockResponse::new(404, ""),
+    ]);
+    let library = test_library("library", &format!("{}/pdf/", server.url()));
+    // PDFでないレスポンスは時間をおいて取得し直す
+    let pdf = library.fetch("2104.01234", 2).unwrap();
+    assert_eq!(pdf.size, 21);
+    assert_eq!(pdf.path, format!("{}/{}.pdf", &pdf.sha256[..2], &pdf.sha256));
+    assert!(library.exists(&pdf.path));
+    assert_eq!(fs::read(library.dir().join(&pdf.path)).unwrap(), b"%PDF-1.4 face parsing");
+    // 同じ内容のPDFは同じファイルになる
+    assert_eq!(library.fetch("2104.01234", 3).unwrap(), pdf);
+    assert!(library.fetch("2104.09999", 1).is_err());
+    assert_eq!(server.requests(), vec!["/pdf/2104.01234v2", "/pdf/2104.01234v2", "/pdf/2104.01234v3", "/pdf/2104.09999v1"]);
+    fs::remove_dir_all(library.dir()).unwrap();
+}
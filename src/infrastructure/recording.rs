@@ -0,0 +1,56 @@
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// 取得したレスポンスを NNNN.xml に、リクエストしたURLなどを NNNN.json に保存する
+#[derive(Serialize, Deserialize, Debug)]
+struct Recording {
+    url: String,
+    fetched: String,
+    file: String,
+}
+
+fn recordings(dir: &Path) -> anyhow::Result<Vec<Recording>> {
+    let mut recordings = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            recordings.push(serde_json::from_str(&fs::read_to_string(&path)?)?);
+        }
+    }
+    Ok(recordings)
+}
+
+pub fn record(dir: &Path, url: &str, body: &str) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)?;
+    let n = recordings(dir)?.len();
+    let file = format!("{:04}.xml", n);
+    fs::write(dir.join(&file), body)?;
+    let recording = Recording {
+        url: url.to_string(),
+        fetched: Utc::now().to_rfc3339(),
+        file,
+    };
+    fs::write(dir.join(format!("{:04}.json", n)), serde_json::to_string_pretty(&recording)?)?;
+    Ok(())
+}
+
+// lookback_daysなどでは投稿日時の範囲の終わりが実行時刻になり、記録したときとURLが変わるので範囲を除いて比べる
+fn without_submitted_date(url: &str) -> String {
+    let re = Regex::new(r"submittedDate:(?:\[|%5B)\d+\+TO\+\d+(?:\]|%5D)").unwrap();
+    re.replace_all(url, "submittedDate:").to_string()
+}
+
+pub fn replay(dir: &Path, url: &str) -> anyhow::Result<String> {
+    let recordings = recordings(dir)?;
+    // 同じURLを何度も記録していた場合は最新のものを使う
+    let latest = |matches: &dyn Fn(&Recording) -> bool| recordings.iter()
+        .filter(|r| matches(r))
+        .max_by(|a, b| a.file.cmp(&b.file));
+    let recording = latest(&|r| r.url == url)
+        .or_else(|| latest(&|r| without_submitted_date(&r.url) == without_submitted_date(url)))
+        .ok_or_else(|| anyhow::anyhow!("No recorded response for {} in {}", url, dir.display()))?;
+    Ok(fs::read_to_string(dir.join(&recording.file))?)
+}
@@ -0,0 +1,43 @@
+use super::arxiv_paper::ArxivPaperRepository;
+use super::database::DbConnection;
+use super::memory::{
+    InMemoryArxivPaperRepository, InMemoryOaiHarvestRepository, InMemorySlackNotificationRepository,
+    InMemoryStore, InMemorySubscriptionStateRepository,
+};
+use super::oai_harvest::OaiHarvestRepository;
+use super::pdf_library::PdfLibrary;
+use super::slack_notifications::SlackNotificationRepository;
+use super::subscription_state::SubscriptionStateRepository;
+use crate::domain::arxiv_paper::ArxivPaperRepositoryTrait;
+use crate::domain::oai_harvest::OaiHarvestRepositoryTrait;
+use crate::domain::slack_notification::SlackNotificationRepositoryTrait;
+use crate::domain::subscription_state::SubscriptionStateRepositoryTrait;
+
+// 一回の実行で使うリポジトリ。DBに保存するか、--ephemeralでメモリ上に保存するかを実行時に選ぶ
+pub struct Repositories {
+    pub arxiv_paper: Box<dyn ArxivPaperRepositoryTrait>,
+    pub slack_notification: Box<dyn SlackNotificationRepositoryTrait>,
+    pub subscription_state: Box<dyn SubscriptionStateRepositoryTrait>,
+    pub oai_harvest: Box<dyn OaiHarvestRepositoryTrait>,
+}
+
+impl Repositories {
+    pub fn database(conn: &DbConnection, library: PdfLibrary) -> Self {
+        Repositories {
+            arxiv_paper: Box::new(ArxivPaperRepository::with_library(conn.clone(), library)),
+            slack_notification: Box::new(SlackNotificationRepository::new(conn.clone())),
+            subscription_state: Box::new(SubscriptionStateRepository::new(conn.clone())),
+            oai_harvest: Box::new(OaiHarvestRepository::new(conn.clone())),
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        let store = InMemoryStore::default();
+        Repositories {
+            arxiv_paper: Box::new(InMemoryArxivPaperRepository::new(store.clone())),
+            slack_notification: Box::new(InMemorySlackNotificationRepository::new(store.clone())),
+            subscription_state: Box::new(InMemorySubscriptionStateRepository::new(store.clone())),
+            oai_harvest: Box::new(InMemoryOaiHarvestRepository::new(store)),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest;
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use std::{thread, time::Duration};
+
+#[derive(Debug)]
+pub enum RetryError {
+    // 時間をおけば成功する可能性があるエラー
+    Transient {
+        error: anyhow::Error,
+        retry_after: Option<Duration>,
+    },
+    Fatal(anyhow::Error),
+}
+
+impl RetryError {
+    pub fn transient(error: anyhow::Error) -> Self {
+        RetryError::Transient { error, retry_after: None }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(3),
+            max_delay: Duration::from_secs(60),
+            jitter: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // attempt回目の失敗後に待つ時間 (base_delay * 2^(attempt-1) + jitter)
+    fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay
+            .checked_mul(2u32.saturating_pow(attempt - 1))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let jitter = match self.jitter.as_millis() as u64 {
+            0 => 0,
+            j => rand::thread_rng().gen_range(0..=j),
+        };
+        backoff + Duration::from_millis(jitter)
+    }
+
+    pub fn run<T, F>(&self, mut f: F) -> anyhow::Result<T>
+        where F: FnMut() -> Result<T, RetryError>
+    {
+        let mut attempt = 1;
+        loop {
+            match f() {
+                Ok(t) => return Ok(t),
+                Err(RetryError::Fatal(error)) => return Err(error),
+                Err(RetryError::Transient { error, retry_after }) => {
+                    if attempt >= self.max_attempts {
+                        return Err(error.context(format!("gave up after {} attempts", attempt)));
+                    }
+                    // 極端に長いRetry-Afterで止まらないよう、max_delayで打ち切る
+                    let delay = retry_after
+                        .map(|d| d.min(self.max_delay))
+                        .unwrap_or_else(|| self.delay(attempt));
+                    eprintln!("{} (attempt {}/{}), retrying in {:?}", error, attempt, self.max_attempts, delay);
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
+pub fn get_text(url: &str) -> Result<String, RetryError> {
+    get(url)?.text().map_err(|e| RetryError::transient(e.into()))
+}
+
+// PDFなどのバイナリを取得する
+pub fn get_bytes(url: &str) -> Result<Vec<u8>, RetryError> {
+    Ok(get(url)?.bytes().map_err(|e| RetryError::transient(e.into()))?.to_vec())
+}
+
+fn get(url: &str) -> Result<reqwest::blocking::Response, RetryError> {
+    let response = reqwest::blocking::get(url).map_err(|e| RetryError::transient(e.into()))?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        return Err(RetryError::Transient {
+            error: anyhow::anyhow!("{} returned {}", url, status),
+            retry_after,
+        });
+    }
+    if !status.is_success() {
+        return Err(RetryError::Fatal(anyhow::anyhow!("{} returned {}", url, status)));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+fn no_wait_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(0),
+        max_delay: Duration::from_millis(0),
+        jitter: Duration::from_millis(0),
+    }
+}
+
+#[test]
+fn test_retry_on_service_unavailable() {
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(503, "").header("Retry-After", "0"),
+        MockResponse::new(503, ""),
+        MockResponse::new(200, "ok"),
+    ]);
+    let body = no_wait_policy().run(|| get_text(&server.url())).unwrap();
+    assert_eq!(body, "ok");
+    assert_eq!(server.requests().len(), 3);
+
+    let server = MockServer::start(vec![MockResponse::new(503, "")]);
+    assert!(no_wait_policy().run(|| get_text(&server.url())).is_err());
+    assert_eq!(server.requests().len(), 3);
+
+    let server = MockServer::start(vec![MockResponse::new(400, "")]);
+    assert!(no_wait_policy().run(|| get_text(&server.url())).is_err());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn test_retry_after_is_capped() {
+    use crate::mock_server::{MockResponse, MockServer};
+    let server = MockServer::start(vec![
+        MockResponse::new(429, "").header("Retry-After", "86400"),
+        MockResponse::new(200, "ok"),
+    ]);
+    let started = std::time::Instant::now();
+    let body = no_wait_policy().run(|| get_text(&server.url())).unwrap();
+    assert_eq!(body, "ok");
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
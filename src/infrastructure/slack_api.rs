@@ -2,7 +2,12 @@ pub mod block;
 
 use reqwest;
 use crate::domain::slack_api::SlackAPITrait;
-use crate::domain::arxiv_paper::Paper;
+use crate::domain::arxiv_paper::{Paper, PaperVersion};
+use crate::domain::author_name::find_followed;
+use crate::domain::latex::to_unicode;
+use crate::domain::paper_link::LinkKind;
+use crate::domain::text_diff::{is_changed, word_diff, Diff};
+use crate::domain::venue::AcceptanceStatus;
 use super::reqwest::header::{CONTENT_TYPE, HeaderValue};
 
 pub struct SlackAPI {
@@ -28,11 +33,11 @@ impl SlackAPITrait for SlackAPI {
             .unwrap();
     }
 
-    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>) -> Vec<(Paper, String)> {
+    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> Vec<(Paper, String)> {
         let mut blocks: Vec<(usize, Paper, String)> = papers.iter().map(|p| {
             let mut section = block::Section::new();
             let n_stars = get_stars(&p, &keywords);
-            let text = get_text(&p, &keywords);
+            let text = get_text(&p, &keywords, follow_authors);
             section.text(&text).block_id(&p.url);
 
             let mut category_button = block::Button::new();
@@ -49,8 +54,35 @@ impl SlackAPITrait for SlackAPI {
 
             let mut actions = block::Actions::new();
             actions.button(&web_button).button(&pdf_button);
+            if let Some(doi) = &p.doi {
+                let mut doi_button = block::Button::new();
+                doi_button.text(":books: DOI").url(&format!("https://doi.org/{}", doi)).action_id(&format!("b_doi_{}", doi));
+                actions.button(&doi_button);
+            }
+            // 種類ごとに最初に書かれているリンクだけボタンにする
+            // action_idは255文字までなので、URLではなく論文のIDから作る
+            let link_buttons = [
+                (LinkKind::Code, ":computer: Code"),
+                (LinkKind::Project, ":house: Project"),
+                (LinkKind::Dataset, ":floppy_disk: Dataset"),
+            ];
+            for (kind, text) in link_buttons.iter() {
+                if let Some(link) = p.links.iter().find(|l| &l.kind == kind) {
+                    let mut link_button = block::Button::new();
+                    link_button.text(text).url(&link.url).action_id(&format!("b_{}_{}", kind.as_str(), p.id.0));
+                    actions.button(&link_button);
+                }
+            }
 
-            (n_stars, p.to_owned(), json!({"blocks": [block::Block::Section(section), block::Block::Actions(actions)]}).to_string())
+            let mut message_blocks = vec![block::Block::Section(section)];
+            // ページ数・図の数・表の数が分かればコンテキストとして小さく表示する
+            if !p.counts.is_empty() {
+                let mut context = block::Context::new();
+                context.text(&format!(":page_facing_up: {}", p.counts.label()));
+                message_blocks.push(block::Block::Context(context));
+            }
+            message_blocks.push(block::Block::Actions(actions));
+            (n_stars, p.to_owned(), json!({"blocks": message_blocks}).to_string())
         }).collect();
         blocks.sort_by(|a, b| a.0.cmp(&b.0).reverse());
         let blocks: Vec<(Paper, String)> = blocks.iter()
@@ -60,14 +92,46 @@ impl SlackAPITrait for SlackAPI {
     }
 }
 
-fn get_text(paper: &Paper, keywords: &Option<Vec<String>>) -> String {
+fn get_text(paper: &Paper, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> String {
     let n_stars = get_stars(&paper, &keywords);
     let stars = ":star:".to_string().repeat(n_stars);
 
-    let is_new = paper.published.eq(&paper.updated);
+    // バージョンが分かる場合はv1かどうか、分からない場合は公開日時と更新日時で判断する
+    let is_new = match paper.arxiv_id.as_ref().and_then(|id| id.version) {
+        Some(version) => version == 1,
+        None => paper.published.eq(&paper.updated),
+    };
     let kazari = if is_new { ":new2:" } else { ":updated:" };
+    let changes = match paper.previous_version() {
+        Some(previous) => get_changes(previous, paper),
+        None => "".to_string(),
+    };
+
+    let comments = format!("> {}\n", to_unicode(&paper.comment));
+    let venue = match &paper.venue {
+        Some(v) => match v.status {
+            AcceptanceStatus::Accepted => format!(":trophy: *{}*\n", v.label()),
+            AcceptanceStatus::UnderReview => format!(":hourglass_flowing_sand: {} (under review)\n", v.label()),
+            AcceptanceStatus::Unknown => format!(":label: {}\n", v.label()),
+        },
+        None => "".to_string(),
+    };
+    let journal_ref = match &paper.journal_ref {
+        Some(j) => format!(":books: {}\n", j),
+        None => "".to_string(),
+    };
 
-    let comments = format!("> {}\n", &paper.comment);
+    let authors: Vec<String> = paper.authors.iter().map(|a| a.name.to_owned()).collect();
+    let followed = match follow_authors {
+        Some(f) => find_followed(&authors, f),
+        None => vec![],
+    };
+    let followed = if followed.is_empty() {
+        "".to_string()
+    } else {
+        format!(":eyes: followed author: {}\n",
+            followed.iter().map(|a| a.as_str()).collect::<Vec<&str>>().join(", "))
+    };
 
     let time = format!("公開 {} 変更 {}\n",
         &paper.published.format("%Y/%m/%d %H:%M").to_string(),
@@ -75,25 +139,134 @@ fn get_text(paper: &Paper, keywords: &Option<Vec<String>>) -> String {
     );
 
     const SLACK_MESSAGE_TRIM: usize = 200;
-    let mut summary = paper.summary.to_owned();
-    if summary.len() >= SLACK_MESSAGE_TRIM {
-        summary = format!("{}...", &summary[0..SLACK_MESSAGE_TRIM - 1]);
+    let mut summary = to_unicode(&paper.summary);
+    // 変換後は複数バイトの文字が入るので文字数で切る
+    if summary.chars().count() >= SLACK_MESSAGE_TRIM {
+        summary = format!("{}...", summary.chars().take(SLACK_MESSAGE_TRIM - 1).collect::<String>());
     }
-    let text = format!("{}{} *{}*\n{}{}{}", &kazari, stars, &paper.title, &time, comments, summary);
+    let text = format!("{}{} *{}*\n{}{}{}{}{}{}{}", &kazari, stars, to_unicode(&paper.title), &venue, &followed, &time, comments, journal_ref, changes, summary);
     text
 }
 
-fn get_stars(paper: &Paper, keywords: &Option<Vec<String>>) -> usize {
+// 一つ前のバージョンからのタイトル・コメント・アブストラクトの変更点
+fn get_changes(previous: &PaperVersion, paper: &Paper) -> String {
+    let fields = [
+        ("title", &previous.title, &paper.title),
+        ("comment", &previous.comment, &paper.comment),
+        ("abstract", &previous.summary, &paper.summary),
+    ];
+    fields.iter()
+        .map(|(name, old, new)| (name, to_unicode(old), to_unicode(new)))
+        .filter(|(_, old, new)| is_changed(old, new))
+        .map(|(name, old, new)| format!(":pencil2: {} (v{}から) {}\n", name, previous.version, format_diff(&word_diff(&old, &new))))
+        .collect()
+}
+
+// 削除は~取り消し線~、追加は*太字*にする。変更のない部分は前後の数語だけ残す
+fn format_diff(diffs: &[Diff]) -> String {
+    const CONTEXT: usize = 5;
+    diffs.iter().enumerate().map(|(i, d)| match d {
+        Diff::Removed(t) => format!("~{}~", t),
+        Diff::Added(t) => format!("*{}*", t),
+        Diff::Same(t) => {
+            let words: Vec<&str> = t.split(' ').collect();
+            let head = if i == 0 { 0 } else { CONTEXT };
+            let tail = if i == diffs.len() - 1 { 0 } else { CONTEXT };
+            if words.len() <= head + tail {
+                return t.to_owned();
+            }
+            let mut parts = vec![];
+            if head > 0 {
+                parts.push(words[..head].join(" "));
+            }
+            parts.push("…".to_string());
+            if tail > 0 {
+                parts.push(words[words.len() - tail..].join(" "));
+            }
+            parts.join(" ")
+        }
+    }).collect::<Vec<String>>().join(" ")
+}
+
+#[test]
+fn test_format_diff() {
+    let diffs = word_diff(
+        "We propose a simple method for face parsing that works in real time on mobile devices.",
+        "We propose a simple and robust method for face parsing that works in real time on mobile devices.",
+    );
+    assert_eq!(format_diff(&diffs), "We propose a simple *and robust* method for face parsing that …");
+    let diffs = word_diff("10 pages", "Accepted to CVPR. 10 pages");
+    assert_eq!(format_diff(&diffs), "*Accepted to CVPR.* 10 pages");
+}
+
+// 投稿先の会議に一致するstar_keywordsの数。slackのメッセージにはこの数だけ星をつける
+// "not yet accepted at CVPR" のような投稿中の論文や、会議名が取り出せなかった論文には星をつけない
+pub fn get_stars(paper: &Paper, keywords: &Option<Vec<String>>) -> usize {
+    let venue = match &paper.venue {
+        Some(venue) if venue.status != AcceptanceStatus::UnderReview => venue,
+        _ => return 0,
+    };
     match keywords {
         Some(k) => {
-            let mut count: usize = 0;
-            for keyword in k {
-                if paper.comment.contains(keyword) {
-                    count += 1
-                }
-            }
-            count
+            // 表記揺れ（NIPSなど）もキーワードにできるよう、コメントとjournal_refも見る
+            let text = format!("{} {} {}",
+                venue.label(),
+                to_unicode(&paper.comment),
+                paper.journal_ref.as_deref().unwrap_or(""));
+            k.iter().filter(|keyword| text.contains(keyword.as_str())).count()
         }
         None => 0usize
     }
 }
+
+#[test]
+fn test_get_stars() {
+    use crate::domain::arxiv_paper::ArxivPaperRepositoryTrait;
+    use crate::domain::venue::{Track, Venue};
+    use super::arxiv_paper::test_paper;
+    use super::memory::{InMemoryArxivPaperRepository, InMemoryStore};
+    let repository = InMemoryArxivPaperRepository::new(InMemoryStore::default());
+    let stars = |id: u32, venue: Option<Venue>| {
+        let url = format!("http://arxiv.org/abs/2104.{:05}v1", id);
+        let mut paper = test_paper(&url, "Title", &["Taro Yamada"], "cs.CV");
+        paper.venue = venue;
+        repository.save(&vec![paper]).unwrap();
+        let paper = repository.find_by_urls(&vec![url]).unwrap().remove(0);
+        get_stars(&paper, &Some(vec!["CVPR".to_string(), "oral".to_string()]))
+    };
+    let venue = |status: AcceptanceStatus| Some(Venue {
+        name: "CVPR".to_string(),
+        year: Some(2021),
+        track: Some(Track::Oral),
+        status,
+    });
+    // コメントに会議名があっても、投稿先が取り出せていなければ星はつけない
+    assert_eq!(stars(1, None), 0);
+    assert_eq!(stars(2, venue(AcceptanceStatus::Accepted)), 2);
+    assert_eq!(stars(3, venue(AcceptanceStatus::UnderReview)), 0);
+}
+
+#[test]
+fn test_link_button_action_id() {
+    use crate::domain::arxiv_paper::ArxivPaperRepositoryTrait;
+    use crate::domain::paper_link::PaperLink;
+    use crate::domain::slack_api::SlackAPITrait;
+    use super::arxiv_paper::test_paper;
+    use super::memory::{InMemoryArxivPaperRepository, InMemoryStore};
+    let repository = InMemoryArxivPaperRepository::new(InMemoryStore::default());
+    let url = "http://arxiv.org/abs/2104.00001v1".to_string();
+    let mut paper = test_paper(&url, "Title", &["Taro Yamada"], "cs.CV");
+    paper.links = vec![PaperLink {
+        kind: LinkKind::Code,
+        url: format!("https://github.com/example/{}", "a".repeat(300)),
+    }];
+    repository.save(&vec![paper]).unwrap();
+    let papers = repository.find_by_urls(&vec![url]).unwrap();
+    let messages = SlackAPI::new("").build_messages(&papers, &None, &None);
+    let action_id = regex::Regex::new(r#""action_id":"([^"]*)""#).unwrap();
+    let ids: Vec<&str> = action_id.captures_iter(&messages[0].1)
+        .map(|caps| caps.get(1).unwrap().as_str())
+        .collect();
+    assert!(ids.contains(&format!("b_code_{}", papers[0].id.0).as_str()));
+    assert!(ids.iter().all(|id| id.len() <= 255));
+}
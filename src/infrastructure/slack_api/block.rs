@@ -63,6 +63,7 @@ pub enum Block
 {
     Section(Section),
     Actions(Actions),
+    Context(Context),
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -85,6 +86,13 @@ pub struct Actions {
     elements: Vec<Accessory>
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Context {
+    #[serde(rename(serialize = "type"))]
+    context_type: String,
+    elements: Vec<TextObject>
+}
+
 impl Section {
     pub fn new() -> Self {
         Section {
@@ -131,6 +139,25 @@ impl Actions {
     }
 }
 
+impl Context {
+    pub fn new() -> Self {
+        Context {
+            context_type: "context".to_string(),
+            elements: vec![]
+        }
+    }
+
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.elements.push(TextObject {
+            block_text_type: TextType::Markdown,
+            text: text.to_string(),
+            emoji: None,
+            verbatim: Some(true),
+        });
+        self
+    }
+}
+
 impl Button {
     pub fn new() -> Self {
         Button{
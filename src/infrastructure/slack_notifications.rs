@@ -1,11 +1,14 @@
 use diesel::prelude::*;
 use chrono::NaiveDateTime;
+use std::collections::HashMap;
 
-use super::sqlite::SQLite;
-use crate::db::models::{SlackNotification, Paper, Category};
+use super::arxiv_paper::{find_affiliations, find_categories, find_classes, find_counts, find_links, find_venues, find_versions};
+use super::database::{insert_or_ignore, DbConnection};
+use crate::db::models::{SlackNotification, Paper, Category, Author};
 use crate::db::schema::*;
 use crate::domain::slack_notification as I;
 use crate::domain::arxiv_paper as J;
+use crate::domain::arxiv_id::ArxivId;
 
 #[derive(Insertable, Debug)]
 #[table_name = "slack_notifications"]
@@ -16,11 +19,13 @@ struct NewSlackNotification {
 }
 
 #[derive(Clone)]
-pub struct SlackNotificationRepository {}
+pub struct SlackNotificationRepository {
+    conn: DbConnection,
+}
 
 impl SlackNotificationRepository {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(conn: DbConnection) -> Self {
+        Self { conn }
     }
 }
 
@@ -31,59 +36,131 @@ impl I::SlackNotificationRepositoryTrait for SlackNotificationRepository {
             slack_url: n.slack_url.clone(),
             updated_at: n.updated,
         }).collect::<Vec<NewSlackNotification>>();
-        let conn = SQLite::create().connect();
-        let n = diesel::insert_or_ignore_into(slack_notifications::table)
-            .values(&new_notifications)
-            .execute(&conn)?;
+        let n = with_connection!(self.conn, |conn| insert_or_ignore(conn, slack_notifications::table, &new_notifications))?;
         Ok(n)
     }
 
     fn find_not_send(&self, slack_url: &str) -> anyhow::Result<Vec<J::Paper>> {
-        let conn = SQLite::create().connect();
-        let notifications: Vec<(SlackNotification, Paper, Category)> = slack_notifications::table
+        let notifications: Vec<(SlackNotification, Paper, Category)> = with_connection!(self.conn, |conn| slack_notifications::table
             .inner_join(papers::table
                 .inner_join(categories::table))
             .filter(slack_notifications::slack_url.eq(slack_url.to_string())
                 .and(slack_notifications::send.eq(false)))
             .select((slack_notifications::all_columns, papers::all_columns, categories::all_columns))
-            .load::<(SlackNotification, Paper, Category)>(&conn)?;
+            .load::<(SlackNotification, Paper, Category)>(conn))?;
+        let paper_authors: Vec<(i32, Author)> = with_connection!(self.conn, |conn| paper_authors::table
+            .inner_join(authors::table)
+            .filter(paper_authors::paper_id.eq_any(
+                notifications.iter().map(|n| n.1.id).collect::<Vec<i32>>()))
+            .select((paper_authors::paper_id, authors::all_columns))
+            .load(conn))?;
+        let paper_ids: Vec<i32> = notifications.iter().map(|n| n.1.id).collect();
+        let categories_map = find_categories(&self.conn, &paper_ids)?;
+        let classes_map = find_classes(&self.conn, &paper_ids)?;
+        let affiliations_map = find_affiliations(&self.conn, &paper_ids)?;
+        let versions_map = find_versions(&self.conn, &paper_ids)?;
+        let venues_map = find_venues(&self.conn, &paper_ids)?;
+        let links_map = find_links(&self.conn, &paper_ids)?;
+        let counts_map = find_counts(&self.conn, &paper_ids)?;
+        let mut authors_map: HashMap<i32, Vec<J::Author>> = HashMap::new();
+        for (paper_id, a) in paper_authors {
+            authors_map.entry(paper_id).or_default()
+                .push(J::Author {
+                    id: a.id,
+                    name: a.name,
+                    affiliations: affiliations_map.get(&(paper_id, a.id)).cloned().unwrap_or_default(),
+                });
+        }
         Ok(notifications.iter().map(|n| J::Paper {
             id: J::PaperId(n.1.id),
+            arxiv_id: ArxivId::parse(&n.1.url).ok(),
             title: n.1.title.to_string(),
             url: n.1.url.to_string(),
             pdf_url: n.1.pdf_url.to_string(),
-            authors: vec![],
+            authors: authors_map.get(&n.1.id).cloned().unwrap_or_default(),
             category: J::Category {
                 id: n.2.id,
                 name: n.2.name.to_string(),
             },
+            categories: categories_map.get(&n.1.id).cloned().unwrap_or_default(),
             summary: n.1.summary.to_string(),
             comment: n.1.comment.to_string(),
             is_accepted: n.1.accepted > 0,
+            journal_ref: n.1.journal_ref.clone(),
+            doi: n.1.doi.clone(),
+            classes: classes_map.get(&n.1.id).cloned().unwrap_or_default(),
+            venue: venues_map.get(&n.1.id).cloned(),
+            links: links_map.get(&n.1.id).cloned().unwrap_or_default(),
+            counts: counts_map.get(&n.1.id).cloned().unwrap_or_default(),
+            versions: versions_map.get(&n.1.id).cloned().unwrap_or_default(),
             updated: n.1.updated,
             published: n.1.published,
         }).collect())
     }
 
+    fn find_notified(&self, slack_url: &str, paper_ids: &[J::PaperId]) -> anyhow::Result<Vec<J::PaperId>> {
+        let notified: Vec<i32> = with_connection!(self.conn, |conn| slack_notifications::table
+            .filter(slack_notifications::slack_url.eq(slack_url)
+                .and(slack_notifications::paper_id.eq_any(
+                    paper_ids.iter().map(|id| id.0).collect::<Vec<i32>>())))
+            .select(slack_notifications::paper_id)
+            .distinct()
+            .load(conn))?;
+        Ok(notified.into_iter().map(J::PaperId).collect())
+    }
+
     fn mark_as_send(&self, slack_url: &str, paper_id: &J::PaperId) -> anyhow::Result<usize> {
-        let conn = SQLite::create().connect();
         let target = slack_notifications::table
             .filter(
                 slack_notifications::paper_id.eq(&paper_id.0)
                     .and(slack_notifications::slack_url.eq(slack_url)));
-        let n_update = diesel::update(target)
+        let n_update = with_connection!(self.conn, |conn| diesel::update(target)
             .set(slack_notifications::send.eq(true))
-            .execute(&conn)?;
+            .execute(conn))?;
         Ok(n_update)
     }
 
     fn delete(&self, slack_url: &str, paper_id: &J::PaperId) -> anyhow::Result<()> {
-        let conn = SQLite::create().connect();
         let target = slack_notifications::table
             .filter(slack_notifications::paper_id.eq(&paper_id.0)
                     .and(slack_notifications::slack_url.eq(slack_url)));
-        diesel::delete(target)
-            .execute(&conn)?;
+        with_connection!(self.conn, |conn| diesel::delete(target)
+            .execute(conn))?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_notifications() {
+    use super::arxiv_paper::{test_paper, ArxivPaperRepository};
+    use super::database::test_connections;
+    use J::ArxivPaperRepositoryTrait;
+    use I::SlackNotificationRepositoryTrait;
+    let slack_url = "https://hooks.slack.com/services/test";
+    for conn in test_connections() {
+        let url = "http://arxiv.org/abs/2104.90101v1";
+        ArxivPaperRepository::new(conn.clone())
+            .save(&vec![test_paper(url, "Title", &["Saburo Tanaka"], "test.SN")]).unwrap();
+        let paper_id = ArxivPaperRepository::new(conn.clone())
+            .find_by_urls(&vec![url.to_string()]).unwrap()[0].id.clone();
+
+        let repository = SlackNotificationRepository::new(conn.clone());
+        let notifications = vec![I::NewSlackNotification {
+            paper_id: paper_id.clone(),
+            slack_url: slack_url.to_string(),
+            updated: chrono::NaiveDate::from_ymd(2021, 4, 1).and_hms(0, 0, 0),
+        }];
+        assert_eq!(repository.save(&notifications).unwrap(), 1);
+        // 同じ論文の同じバージョンは二回通知しない
+        assert_eq!(repository.save(&notifications).unwrap(), 0, "{}", conn.backend());
+        let papers = repository.find_not_send(slack_url).unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(papers[0].authors[0].name, "Saburo Tanaka");
+        assert_eq!(repository.find_notified(slack_url, std::slice::from_ref(&paper_id)).unwrap(), vec![paper_id.clone()]);
+
+        assert_eq!(repository.mark_as_send(slack_url, &paper_id).unwrap(), 1);
+        assert!(repository.find_not_send(slack_url).unwrap().is_empty());
+        repository.delete(slack_url, &paper_id).unwrap();
+        assert!(repository.find_notified(slack_url, &[paper_id]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use diesel::prelude::*;
+use chrono::NaiveDateTime;
+
+use super::database::{insert_or_ignore, DbConnection};
+use crate::db::models::SubscriptionState;
+use crate::db::schema::*;
+use crate::domain::arxiv_paper::PaperId;
+use crate::domain::subscription_state as I;
+
+#[derive(Insertable, Debug)]
+#[table_name = "subscription_state"]
+struct NewSubscriptionState {
+    pub subscription: String,
+    pub last_updated: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "subscription_papers"]
+struct NewSubscriptionPaper {
+    pub subscription: String,
+    pub paper_id: i32,
+}
+
+#[derive(Clone)]
+pub struct SubscriptionStateRepository {
+    conn: DbConnection,
+}
+
+impl SubscriptionStateRepository {
+    pub fn new(conn: DbConnection) -> Self {
+        Self { conn }
+    }
+}
+
+impl I::SubscriptionStateRepositoryTrait for SubscriptionStateRepository {
+    fn find(&self, subscription: &str) -> anyhow::Result<Option<I::SubscriptionState>> {
+        let state: Option<SubscriptionState> = with_connection!(self.conn, |conn| subscription_state::table
+            .find(subscription)
+            .first(conn)
+            .optional())?;
+        Ok(state.map(|s| I::SubscriptionState {
+            subscription: s.subscription,
+            last_updated: s.last_updated,
+        }))
+    }
+
+    fn save(&self, state: &I::SubscriptionState) -> anyhow::Result<usize> {
+        // REPLACE INTOはSQLiteにしかないので、消してから入れ直す
+        let n = with_connection!(self.conn, |conn| conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(subscription_state::table.find(&state.subscription))
+                .execute(conn)?;
+            diesel::insert_into(subscription_state::table)
+                .values(&NewSubscriptionState {
+                    subscription: state.subscription.clone(),
+                    last_updated: state.last_updated,
+                })
+                .execute(conn)
+        }))?;
+        Ok(n)
+    }
+
+    fn add_papers(&self, subscription: &str, paper_ids: &[PaperId]) -> anyhow::Result<usize> {
+        let new_papers = paper_ids.iter().map(|id| NewSubscriptionPaper {
+            subscription: subscription.to_string(),
+            paper_id: id.0,
+        }).collect::<Vec<NewSubscriptionPaper>>();
+        let n = with_connection!(self.conn, |conn| insert_or_ignore(conn, subscription_papers::table, &new_papers))?;
+        Ok(n)
+    }
+
+    fn find_paper_ids(&self, subscription: &str) -> anyhow::Result<Vec<PaperId>> {
+        let paper_ids: Vec<i32> = with_connection!(self.conn, |conn| subscription_papers::table
+            .filter(subscription_papers::subscription.eq(subscription))
+            .select(subscription_papers::paper_id)
+            .order(subscription_papers::paper_id)
+            .load(conn))?;
+        Ok(paper_ids.into_iter().map(PaperId).collect())
+    }
+}
+
+#[test]
+fn test_save() {
+    use super::database::test_connections;
+    use I::SubscriptionStateRepositoryTrait;
+    for conn in test_connections() {
+        let repository = SubscriptionStateRepository::new(conn);
+        assert!(repository.find("test").unwrap().is_none());
+        let mut state = I::SubscriptionState {
+            subscription: "test".to_string(),
+            last_updated: chrono::NaiveDate::from_ymd(2021, 4, 1).and_hms(0, 0, 0),
+        };
+        repository.save(&state).unwrap();
+        // 二回目は上書きする
+        state.last_updated = chrono::NaiveDate::from_ymd(2021, 4, 2).and_hms(0, 0, 0);
+        repository.save(&state).unwrap();
+        assert_eq!(repository.find("test").unwrap().unwrap().last_updated, state.last_updated);
+    }
+}
+
+#[test]
+fn test_subscription_papers() {
+    use super::arxiv_paper::{test_paper, ArxivPaperRepository};
+    use super::database::test_connections;
+    use crate::domain::arxiv_paper::ArxivPaperRepositoryTrait;
+    use I::SubscriptionStateRepositoryTrait;
+    for conn in test_connections() {
+        let papers = vec![
+            test_paper("http://arxiv.org/abs/2104.90011v1", "Face", &["Taro Yamada"], "test.AP"),
+            test_paper("http://arxiv.org/abs/2104.90012v1", "Hair", &["Taro Yamada"], "test.AP"),
+        ];
+        let arxiv_paper = ArxivPaperRepository::new(conn.clone());
+        arxiv_paper.save(&papers).unwrap();
+        let ids: Vec<PaperId> = arxiv_paper.find_by_urls(&papers.iter().map(|p| p.url.clone()).collect()).unwrap()
+            .into_iter().map(|p| p.id).collect();
+        let repository = SubscriptionStateRepository::new(conn);
+        assert_eq!(repository.add_papers("face", &ids[..1]).unwrap(), 1);
+        assert_eq!(repository.add_papers("face", &ids).unwrap(), 1);
+        assert_eq!(repository.add_papers("hair", &ids[1..]).unwrap(), 1);
+        assert_eq!(repository.find_paper_ids("face").unwrap().len(), 2);
+        assert_eq!(repository.find_paper_ids("hair").unwrap(), vec![ids[1].clone()]);
+    }
+}
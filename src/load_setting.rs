@@ -7,18 +7,89 @@ use std::io::Read;
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub arxiv: Vec<ArxivConfig>,
+    pub api_url: Option<String>,
+    pub oai_url: Option<String>,
+    pub rss_url: Option<String>,
+    // 会議名の表記揺れのリスト。省略時は埋め込みのvenues.toml
+    pub venues: Option<String>,
+    pub retry: Option<RetryConfig>,
+    // SQLiteのDBのパス。--databaseを指定した場合はそちらを使う
+    pub database: Option<String>,
+    // downloadでPDFを置くディレクトリ。--dirを指定した場合はそちらを使う
+    pub library: Option<String>,
+    pub pdf_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RetryConfig {
+    pub max_attempts: Option<u32>,
+    pub base_delay_millis: Option<u64>,
+    pub max_delay_millis: Option<u64>,
+    pub jitter_millis: Option<u64>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct ArxivConfig {
+    pub name: Option<String>,
+    pub source: Option<String>,
+    #[serde(default)]
     pub categories: Vec<String>,
+    pub query: Option<String>,
     pub search_title_words: Option<Vec<String>>,
     pub exclude_title_words: Option<Vec<String>>,
     pub search_abstract_words: Option<Vec<String>>,
     pub exclude_abstract_words: Option<Vec<String>>,
     pub filter_by_main_category: bool,
+    pub cross_listed_categories: Option<Vec<String>>,
     pub slack: String,
     pub star_keywords: Option<Vec<String>>,
+    pub page_size: Option<u32>,
+    pub max_total_results: Option<u32>,
+    pub api_url: Option<String>,
+    pub filters: Option<Vec<FilterConfig>>,
+    pub lookback_days: Option<i64>,
+    pub follow_authors: Option<Vec<String>>,
+    pub announce_types: Option<Vec<String>>,
+    pub revision_policy: Option<String>,
+    #[serde(default)]
+    pub require_code: bool,
+    pub min_pages: Option<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FilterConfig {
+    pub field: String,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VenuesConfig {
+    pub venue: Vec<VenueConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VenueConfig {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+// 実行ファイルに埋め込んだ既定の会議名リスト
+const DEFAULT_VENUES: &str = include_str!("../venues.toml");
+
+pub fn default_venues() -> anyhow::Result<VenuesConfig> {
+    Ok(toml::from_str(DEFAULT_VENUES)?)
+}
+
+pub fn load_venues(path: &str) -> anyhow::Result<VenuesConfig> {
+    let mut venues_toml = String::new();
+    File::open(path)?.read_to_string(&mut venues_toml)?;
+    Ok(toml::from_str(&venues_toml)?)
 }
 
 pub fn load_config(path: &str) -> anyhow::Result<Config> {
@@ -42,4 +113,17 @@ fn test_load_config() {
     let config = load_config("setting.toml").unwrap();
     println!("{:?}", &config);
     assert_eq!(config.arxiv.len(), 4usize);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_load_venues() {
+    let venues = load_venues("venues.toml").unwrap();
+    let neurips = venues.venue.iter().find(|v| v.name == "NeurIPS").unwrap();
+    assert!(neurips.aliases.contains(&"NIPS".to_string()));
+}
+
+#[test]
+fn test_default_venues() {
+    let venues = default_venues().unwrap();
+    assert!(venues.venue.iter().any(|v| v.name == "NeurIPS"));
+}
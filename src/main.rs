@@ -1,5 +1,7 @@
 #[macro_use]
 extern crate diesel;
+#[macro_use]
+extern crate diesel_migrations;
 extern crate dotenv;
 extern crate percent_encoding;
 extern crate quick_xml;
@@ -9,43 +11,64 @@ extern crate structopt;
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
+use std::path::PathBuf;
 use std::{thread, time};
 
-use crate::usecase::arxiv_paper::ArxivPaperUseCaseTrait;
-
 pub mod db;
 pub mod domain;
 pub mod infrastructure;
 pub mod load_setting;
+#[cfg(test)]
+mod mock_server;
 pub mod usecase;
 
+use anyhow::Context;
+use crate::db::migrations;
+use crate::domain::arxiv_api::Paper as ApiPaper;
+use crate::domain::arxiv_id::ArxivId;
 use crate::domain::arxiv_paper::NewPaper;
-use crate::domain::slack_notification::NewSlackNotification;
+use crate::domain::venue::{VenueAlias, VenueExtractor};
+use crate::domain::slack_api::SlackAPITrait;
 use crate::usecase::arxiv_api::ArxivAPIUseCaseTrait;
-use crate::usecase::slack_api::SlackAPIUseCaseTrait;
-use crate::usecase::slack_notifications::SlackNotificationUseCaseTrait;
-use infrastructure::arxiv_api::{ArxivAPI, SortBy, SortOrder};
-use infrastructure::arxiv_paper::ArxivPaperRepository;
-use infrastructure::slack_api::SlackAPI;
-use infrastructure::slack_notifications::SlackNotificationRepository;
-use load_setting::load_config;
+use crate::usecase::arxiv_paper::ArxivPaperUseCaseTrait;
+use crate::usecase::oai_harvest::OaiHarvestUseCaseTrait;
+use crate::usecase::subscription_state::SubscriptionStateUseCaseTrait;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use infrastructure::arxiv_api::{query, ArxivAPI, SortBy, SortOrder};
+use infrastructure::arxiv_oai::{set_of_category, ArxivOAI, MetadataPrefix};
+use infrastructure::arxiv_rss::{AnnounceType, ArxivRssSource};
+use infrastructure::memory::InMemorySlackAPI;
+use infrastructure::pdf_library::{default_library, PdfLibrary};
+use infrastructure::repositories::Repositories;
+use infrastructure::retry::RetryPolicy;
+use infrastructure::slack_api::{get_stars, SlackAPI};
+use infrastructure::database::{DatabaseUrl, DbConnection};
+use load_setting::{default_venues, load_config, load_venues, ArxivConfig, Config, FilterConfig, RetryConfig};
 use structopt::clap;
 use structopt::clap::arg_enum;
 use structopt::StructOpt;
 use usecase::arxiv_api::ArxivAPIUseCase;
-use usecase::arxiv_paper::{extract_paper_urls, ArxivPaperUseCase};
+use usecase::arxiv_paper::ArxivPaperUseCase;
+use usecase::oai_harvest::OaiHarvestUseCase;
+use usecase::paper_filter::{CategoryRule, FilterField, FilterRule, PaperFilter};
+use usecase::pipeline::{find_subscription_papers, record_subscription, save_and_enqueue, send_queued};
+use usecase::revision_policy::RevisionPolicy;
 use usecase::slack_api::SlackAPIUseCase;
 use usecase::slack_notifications::SlackNotificationUseCase;
+use usecase::subscription_state::SubscriptionStateUseCase;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "arXiv API")]
 #[structopt(setting(clap::AppSettings::ColoredHelp))]
 pub struct Opt {
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+
     #[structopt(short, long)]
     max_results: Option<u32>,
 
     #[structopt(short, long)]
-    start: u32,
+    start: Option<u32>,
 
     #[structopt(long = "slack")]
     slack: bool,
@@ -56,8 +79,109 @@ pub struct Opt {
     #[structopt(long = "save")]
     save: bool,
 
+    /// この日時より後に更新された論文だけを取得する（前回の取得位置を無視する）
+    #[structopt(long = "since", parse(try_from_str = parse_date), conflicts_with = "full-resync")]
+    since: Option<NaiveDateTime>,
+
+    /// この日以降に投稿された論文を取得する（前回の取得位置を無視する）
+    #[structopt(long = "from", parse(try_from_str = parse_date))]
+    from: Option<NaiveDateTime>,
+
+    /// この日までに投稿された論文を取得する
+    #[structopt(long = "to", parse(try_from_str = parse_date), requires = "from")]
+    to: Option<NaiveDateTime>,
+
+    /// 前回の取得位置を無視して全件取得し直す
+    #[structopt(long = "full-resync")]
+    full_resync: bool,
+
+    /// arXiv APIのレスポンスをディレクトリに保存する
+    #[structopt(long = "record", parse(from_os_str), conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// arXiv APIにアクセスせず、--recordで保存したレスポンスを使う
+    #[structopt(long = "replay", parse(from_os_str))]
+    replay: Option<PathBuf>,
+
+    /// filtersで各論文を残した/落とした理由を表示する
+    #[structopt(long = "debug-filter")]
+    debug_filter: bool,
+
+    /// DBを使わずにメモリ上で実行する。保存した論文は終了時に消え、slackには送らずにメッセージを表示する
+    #[structopt(long = "ephemeral", conflicts_with = "database")]
+    ephemeral: bool,
+
+    /// SQLiteのDBのパス、またはpostgres://で始まるPostgreSQLのURL（省略時はsetting.tomlのdatabase、環境変数DATABASE_URL、database/arxiv-bot.dbの順に使う）
+    #[structopt(long = "database")]
+    database: Option<String>,
+
     #[structopt(possible_values = & OptSortBy::variants(), case_insensitive = false)]
-    sort_by: OptSortBy,
+    sort_by: Option<OptSortBy>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// arXiv IDを指定して論文を取得し、DBに保存する
+    Add {
+        #[structopt(required = true)]
+        ids: Vec<String>,
+
+        /// 保存した論文をこのwebhook URLへの送信キューに入れる
+        #[structopt(long = "slack-url", conflicts_with = "subscription")]
+        slack_url: Option<String>,
+
+        /// 保存した論文をこの名前の購読のslackへの送信キューに入れる
+        #[structopt(long = "subscription")]
+        subscription: Option<String>,
+    },
+    /// OAI-PMHで論文をまとめて取得し、DBに保存する（slackには送らない）
+    Harvest {
+        /// 取得するset（例: cs, physics:hep-th）
+        #[structopt(long = "set")]
+        set: Option<String>,
+
+        /// この日以降に更新された論文を取得する
+        #[structopt(long = "from", parse(try_from_str = parse_date))]
+        from: Option<NaiveDateTime>,
+
+        /// この日までに更新された論文を取得する
+        #[structopt(long = "until", parse(try_from_str = parse_date))]
+        until: Option<NaiveDateTime>,
+
+        #[structopt(long = "metadata-prefix", default_value = "arXiv", possible_values = &["arXiv", "arXivRaw"])]
+        metadata_prefix: String,
+
+        /// 中断した取得を再開せずに最初からやり直す
+        #[structopt(long = "restart")]
+        restart: bool,
+    },
+    /// 購読のslackに通知した論文のPDFをダウンロードし、DBに記録する
+    Download {
+        /// この名前の購読の論文をダウンロードする
+        #[structopt(long = "subscription")]
+        subscription: String,
+
+        /// star_keywordsに一致する（slackで星がつく）論文だけをダウンロードする
+        #[structopt(long = "starred-only")]
+        starred_only: bool,
+
+        /// PDFを置くディレクトリ（省略時はsetting.tomlのlibrary、それもなければlibrary/）
+        #[structopt(long = "dir", parse(from_os_str))]
+        dir: Option<PathBuf>,
+    },
+    /// DBのマイグレーションを管理する
+    Db {
+        #[structopt(subcommand)]
+        cmd: DbCommand,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+enum DbCommand {
+    /// バイナリに埋め込んだマイグレーションのうち未適用のものを適用する
+    Migrate,
+    /// マイグレーションごとに適用済みかどうかを表示する
+    Status,
 }
 
 arg_enum! {
@@ -69,35 +193,511 @@ arg_enum! {
     }
 }
 
-fn main() {
+// 購読ごとの論文の取得元
+#[derive(Debug, PartialEq)]
+enum Source {
+    Api,
+    Rss,
+    Oai,
+}
+
+impl Source {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "api" => Ok(Source::Api),
+            "rss" => Ok(Source::Rss),
+            "oai" => Ok(Source::Oai),
+            _ => anyhow::bail!("Unknown source `{}` (use one of api, rss, oai)", name),
+        }
+    }
+}
+
+fn parse_date(s: &str) -> anyhow::Result<NaiveDateTime> {
+    Ok(NaiveDate::parse_from_str(s, "%Y-%m-%d")?.and_hms(0, 0, 0))
+}
+
+fn retry_policy(config: &Option<RetryConfig>) -> RetryPolicy {
+    let default = RetryPolicy::default();
+    match config {
+        Some(c) => RetryPolicy {
+            max_attempts: c.max_attempts.unwrap_or(default.max_attempts),
+            base_delay: c.base_delay_millis.map(time::Duration::from_millis).unwrap_or(default.base_delay),
+            max_delay: c.max_delay_millis.map(time::Duration::from_millis).unwrap_or(default.max_delay),
+            jitter: c.jitter_millis.map(time::Duration::from_millis).unwrap_or(default.jitter),
+        },
+        None => default,
+    }
+}
+
+fn filter_rules(filters: &[FilterConfig]) -> anyhow::Result<Vec<FilterRule>> {
+    filters.iter().map(|f| Ok(FilterRule {
+        field: FilterField::parse(&f.field)?,
+        include: f.include.clone().unwrap_or_default(),
+        exclude: f.exclude.clone().unwrap_or_default(),
+        regex: f.regex,
+        required: f.required,
+    })).collect()
+}
+
+// RSSやOAI-PMHでは検索できないので、タイトルやアブストの単語の条件は取得後に手元で絞り込む
+fn word_rules(c: &ArxivConfig) -> Vec<FilterRule> {
+    let rule = |field: FilterField, include: &Option<Vec<String>>, exclude: &Option<Vec<String>>| FilterRule {
+        field,
+        include: include.clone().unwrap_or_default(),
+        exclude: exclude.clone().unwrap_or_default(),
+        regex: false,
+        required: false,
+    };
+    vec![
+        rule(FilterField::Title, &c.search_title_words, &c.exclude_title_words),
+        rule(FilterField::Abstract, &c.search_abstract_words, &c.exclude_abstract_words),
+    ]
+}
+
+// クロスリストされた論文も取得できるように、cross_listed_categoriesも検索対象にする
+fn search_categories(c: &ArxivConfig) -> Vec<String> {
+    let mut categories = c.categories.clone();
+    for category in c.cross_listed_categories.iter().flatten() {
+        if !categories.contains(category) {
+            categories.push(category.clone());
+        }
+    }
+    categories
+}
+
+fn category_rule(c: &ArxivConfig, source: &Source) -> Option<CategoryRule> {
+    if c.filter_by_main_category {
+        Some(CategoryRule {
+            primary: c.categories.clone(),
+            cross_listed: c.cross_listed_categories.clone().unwrap_or_default(),
+        })
+    } else if *source == Source::Oai {
+        // OAI-PMHのsetはアーカイブ単位なので、どれかのカテゴリに含まれる論文に絞り込む
+        Some(CategoryRule { primary: vec![], cross_listed: search_categories(c) })
+    } else {
+        None
+    }
+}
+
+fn query_rss(config: &Config, c: &ArxivConfig, opt: &Opt) -> anyhow::Result<Vec<ApiPaper>> {
+    let mut arxiv_rss = ArxivRssSource::new(&search_categories(c));
+    arxiv_rss.retry_policy(retry_policy(&config.retry));
+    if let Some(types) = &c.announce_types {
+        let types = types.iter().map(|t| AnnounceType::parse(t)).collect::<anyhow::Result<Vec<AnnounceType>>>()?;
+        arxiv_rss.announce_types(&types);
+    }
+    if let Some(url) = &config.rss_url {
+        arxiv_rss.rss_url(url);
+    }
+    if let Some(dir) = &opt.record {
+        arxiv_rss.record(dir);
+    }
+    if let Some(dir) = &opt.replay {
+        arxiv_rss.replay(dir);
+    }
+    ArxivAPIUseCase::new(arxiv_rss).query()
+}
+
+fn query_oai(config: &Config, c: &ArxivConfig, opt: &Opt, from: NaiveDate, until: Option<NaiveDate>) -> anyhow::Result<Vec<ApiPaper>> {
+    let mut sets: Vec<String> = search_categories(c).iter().map(|c| set_of_category(c)).collect();
+    sets.sort();
+    sets.dedup();
+    let mut papers = vec![];
+    for (i, set) in sets.iter().enumerate() {
+        if i > 0 && opt.replay.is_none() {
+            thread::sleep(time::Duration::from_millis(3000))  // for arxiv api limit
+        }
+        let mut arxiv_oai = ArxivOAI::new(MetadataPrefix::Arxiv, &Some(set.clone()), Some(from), until);
+        arxiv_oai.retry_policy(retry_policy(&config.retry));
+        if let Some(url) = &config.oai_url {
+            arxiv_oai.oai_url(url);
+        }
+        if let Some(dir) = &opt.record {
+            arxiv_oai.record(dir);
+        }
+        if let Some(dir) = &opt.replay {
+            arxiv_oai.replay(dir);
+        }
+        papers.extend(ArxivAPIUseCase::new(arxiv_oai).query()?);
+    }
+    Ok(papers)
+}
+
+fn venue_extractor(config: &Config) -> anyhow::Result<VenueExtractor> {
+    let venues = match &config.venues {
+        Some(path) => load_venues(path).with_context(|| format!("Failed to load venues from {}", path))?,
+        None => default_venues()?,
+    };
+    VenueExtractor::new(&venues.venue.into_iter()
+        .map(|v| VenueAlias { name: v.name, aliases: v.aliases })
+        .collect::<Vec<VenueAlias>>())
+}
+
+fn new_papers(papers: &[ApiPaper], venues: &VenueExtractor) -> Vec<NewPaper> {
+    papers.iter().map(|p| {
+        let mut paper = NewPaper::from(p.clone());
+        paper.venue = venues.extract(&p.comment, p.journal_ref.as_deref());
+        paper
+    }).collect()
+}
+
+fn save_papers(repositories: &Repositories, papers: &Vec<NewPaper>, slack_url: Option<&str>, subscription: Option<&str>, revision_policy: &RevisionPolicy) -> anyhow::Result<()> {
+    let arxiv_paper = ArxivPaperUseCase::new(&*repositories.arxiv_paper);
+    let slack_notification = SlackNotificationUseCase::new(&*repositories.slack_notification);
+
+    let n_papers = save_and_enqueue(&arxiv_paper, &slack_notification, papers, slack_url, revision_policy)?;
+    if let Some(subscription) = subscription {
+        let subscription_state = SubscriptionStateUseCase::new(&*repositories.subscription_state);
+        record_subscription(&arxiv_paper, &subscription_state, subscription, papers)?;
+    }
+    match n_papers {
+        0 => println!("No paper saved"),
+        1 => println!("1 paper saved"),
+        _ => println!("{} papers saved", &n_papers),
+    }
+    Ok(())
+}
+
+fn add(config: &Config, repositories: &Repositories, ids: &[String], slack_url: &Option<String>, subscription: &Option<String>) -> anyhow::Result<()> {
+    let venues = venue_extractor(config)?;
+    let slack_url = match subscription {
+        Some(name) => match config.arxiv.iter().find(|c| c.name.as_ref() == Some(name)) {
+            Some(c) => Some(c.slack.clone()),
+            None => anyhow::bail!("Subscription {} is not found in the config", name),
+        },
+        None => slack_url.clone(),
+    };
+    // URLやarXiv:つきで指定してもよいが、APIにはIDだけを渡す
+    let ids = ids.iter()
+        .map(|id| ArxivId::parse(id).map(|id| id.to_string()))
+        .collect::<anyhow::Result<Vec<String>>>()?;
+
+    let mut arxiv_api_interface = ArxivAPI::with_id_list(&ids);
+    arxiv_api_interface.retry_policy(retry_policy(&config.retry));
+    if let Some(url) = &config.api_url {
+        arxiv_api_interface.api_url(url);
+    }
+    let arxiv_api = ArxivAPIUseCase::new(arxiv_api_interface);
+    let papers = arxiv_api.query()?;
+    match papers.len() {
+        0 => println!("No paper found"),
+        1 => println!("1 paper found"),
+        _ => println!("{} papers found", papers.len()),
+    }
+
+    let papers = new_papers(&papers, &venues);
+    // 明示的に追加した論文は通知済みでも改めて通知する
+    save_papers(repositories, &papers, slack_url.as_deref(), subscription.as_deref(), &RevisionPolicy::AllVersions)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn harvest(config: &Config, repositories: &Repositories, opt: &Opt, set: &Option<String>, from: Option<NaiveDateTime>, until: Option<NaiveDateTime>, metadata_prefix: &str, restart: bool) -> anyhow::Result<()> {
+    let metadata_prefix = MetadataPrefix::parse(metadata_prefix)?;
+    let venues = venue_extractor(config)?;
+    let mut arxiv_oai = ArxivOAI::new(metadata_prefix, set, from.map(|d| d.date()), until.map(|d| d.date()));
+    arxiv_oai.retry_policy(retry_policy(&config.retry));
+    if let Some(url) = &config.oai_url {
+        arxiv_oai.oai_url(url);
+    }
+    if let Some(dir) = &opt.record {
+        arxiv_oai.record(dir);
+    }
+    if let Some(dir) = &opt.replay {
+        arxiv_oai.replay(dir);
+    }
+
+    let oai_harvest = OaiHarvestUseCase::new(&*repositories.oai_harvest);
+    let harvest = arxiv_oai.harvest_key();
+    let mut resumption_token = match restart {
+        true => None,
+        false => oai_harvest.find_resumption_token(&harvest)?,
+    };
+    if let Some(token) = &resumption_token {
+        println!("Resuming harvest from resumptionToken {}", token);
+    }
+    // ページごとに保存してからresumptionTokenを記録するので、中断してもそのページから再開できる
+    loop {
+        // cronから失敗が分かるように、エラーで終了する
+        let (papers, next) = arxiv_oai.harvest_page(resumption_token.as_deref())
+            .with_context(|| format!("Failed to harvest {} (use --restart if the resumption token expired)", harvest))?;
+        println!("{} papers harvested", papers.len());
+        let papers = new_papers(&papers, &venues);
+        save_papers(repositories, &papers, None, None, &RevisionPolicy::AllVersions)?;
+        oai_harvest.save_resumption_token(&harvest, next.as_deref())?;
+
+        resumption_token = next;
+        if resumption_token.is_none() {
+            break;
+        }
+        if opt.replay.is_none() {
+            thread::sleep(time::Duration::from_millis(3000))  // for arxiv api limit
+        }
+    }
+    Ok(())
+}
+
+fn pdf_library(config: &Config, dir: Option<&PathBuf>) -> PdfLibrary {
+    let dir = dir.cloned()
+        .or_else(|| config.library.as_ref().map(PathBuf::from))
+        .unwrap_or_else(default_library);
+    let mut library = PdfLibrary::new(&dir);
+    library.retry_policy(retry_policy(&config.retry));
+    if let Some(url) = &config.pdf_url {
+        library.pdf_url(url);
+    }
+    library
+}
+
+fn download(config: &Config, repositories: &Repositories, subscription: &str, starred_only: bool) -> anyhow::Result<()> {
+    let c = match config.arxiv.iter().find(|c| c.name.as_deref() == Some(subscription)) {
+        Some(c) => c,
+        None => anyhow::bail!("Subscription {} is not found in the config", subscription),
+    };
+    // slackの送信キューは同じwebhookの購読で共有するので、購読ごとに記録した論文を使う
+    let arxiv_paper = ArxivPaperUseCase::new(&*repositories.arxiv_paper);
+    let subscription_state = SubscriptionStateUseCase::new(&*repositories.subscription_state);
+    let mut papers = find_subscription_papers(&arxiv_paper, &subscription_state, subscription)?;
+    if starred_only {
+        papers.retain(|p| get_stars(p, &c.star_keywords) > 0);
+    }
+    match papers.len() {
+        0 => println!("No paper found"),
+        1 => println!("1 paper found"),
+        _ => println!("{} papers found", papers.len()),
+    }
+
+    let files = arxiv_paper.download(&papers)?;
+    match files.len() {
+        0 => println!("No PDF downloaded"),
+        1 => println!("1 PDF downloaded"),
+        _ => println!("{} PDFs downloaded", files.len()),
+    }
+    Ok(())
+}
+
+fn db(conn: &DbConnection, database: &str, cmd: &DbCommand) -> anyhow::Result<()> {
+    match cmd {
+        DbCommand::Migrate => {
+            let pending = migrations::status(conn)?.iter().filter(|s| !s.applied).count();
+            migrations::run(conn, &mut std::io::stdout())?;
+            match pending {
+                0 => println!("{} is up to date", database),
+                1 => println!("1 migration applied to {}", database),
+                _ => println!("{} migrations applied to {}", pending, database),
+            }
+        }
+        DbCommand::Status => {
+            println!("{} ({})", database, conn.backend());
+            for s in migrations::status(conn)? {
+                let state = match (s.applied, s.unknown) {
+                    (true, true) => "applied (not in this binary)",
+                    (true, false) => "applied",
+                    (false, _) => "pending",
+                };
+                println!("{}  {}", s.version, state);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
     const FILE: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/", "setting.toml");
 
-    let config = load_config(FILE).unwrap();
+    let config = load_config(FILE)?;
 
     let opt: Opt = Opt::from_args();
+    let repositories = if opt.ephemeral {
+        Repositories::in_memory()
+    } else {
+        let database = DatabaseUrl::resolve(opt.database.as_deref(), config.database.as_deref());
+        let conn = database.connect()?;
+        if let Some(Command::Db { cmd }) = &opt.cmd {
+            return db(&conn, &database.display(), cmd);
+        }
+        // diesel_cliがなくても動くように、db以外のコマンドでは未適用のマイグレーションを起動時に適用する
+        migrations::run(&conn, &mut std::io::stdout())?;
+        let dir = match &opt.cmd {
+            Some(Command::Download { dir, .. }) => dir.as_ref(),
+            _ => None,
+        };
+        Repositories::database(&conn, pdf_library(&config, dir))
+    };
 
+    match &opt.cmd {
+        Some(Command::Add { ids, slack_url, subscription }) => add(&config, &repositories, ids, slack_url, subscription),
+        Some(Command::Harvest { set, from, until, metadata_prefix, restart }) => {
+            harvest(&config, &repositories, &opt, set, *from, *until, metadata_prefix, *restart)
+        }
+        Some(Command::Download { .. }) if opt.ephemeral => anyhow::bail!("download cannot be used with --ephemeral"),
+        Some(Command::Download { subscription, starred_only, .. }) => download(&config, &repositories, subscription, *starred_only),
+        Some(Command::Db { .. }) => anyhow::bail!("db commands cannot be used with --ephemeral"),
+        None => {
+            // サブコマンドを指定しない場合は従来通りsort_byと--startが必須
+            if opt.sort_by.is_none() || opt.start.is_none() {
+                clap::Error::with_description(
+                    "<sort-by> and --start <start> are required",
+                    clap::ErrorKind::MissingRequiredArgument,
+                ).exit();
+            }
+            run(&config, &repositories, &opt)
+        }
+    }
+}
+
+fn run(config: &Config, repositories: &Repositories, opt: &Opt) -> anyhow::Result<()> {
+    let venues = venue_extractor(config)?;
     for c in &config.arxiv {
-        let arxiv_api_interface = ArxivAPI::new(
-            &c.categories,
+        let source = match c.source.as_deref().map(Source::parse).transpose() {
+            Ok(source) => source.unwrap_or(Source::Api),
+            Err(e) => {
+                eprintln!("Invalid subscription {:?}: {}", c.name, e);
+                continue;
+            }
+        };
+        // 指定しない場合は従来通り新しいバージョンが出るたびに通知する
+        let revision_policy = match c.revision_policy.as_deref().map(RevisionPolicy::parse).transpose() {
+            Ok(policy) => policy.unwrap_or(RevisionPolicy::AllVersions),
+            Err(e) => {
+                eprintln!("Invalid subscription {:?}: {}", c.name, e);
+                continue;
+            }
+        };
+        // rss/oaiは著者で検索できないので、follow_authorsも受け付けない
+        if source != Source::Api && (c.query.is_some() || c.follow_authors.is_some() || c.categories.is_empty()) {
+            eprintln!("Invalid subscription {:?}: source {:?} needs categories and cannot use query or follow_authors", c.name, source);
+            continue;
+        }
+        let mut arxiv_api_interface = ArxivAPI::new(
+            &search_categories(c),
             &c.search_title_words,
             &c.exclude_title_words,
             &c.search_abstract_words,
             &c.exclude_abstract_words,
             match &opt.sort_by {
-                OptSortBy::Relevance => SortBy::Relevance,
-                OptSortBy::LastUpdatedBy => SortBy::LastUpdatedDate,
-                OptSortBy::SubmittedDate => SortBy::SubmittedDate,
+                Some(OptSortBy::Relevance) => SortBy::Relevance,
+                Some(OptSortBy::LastUpdatedBy) | None => SortBy::LastUpdatedDate,
+                Some(OptSortBy::SubmittedDate) => SortBy::SubmittedDate,
             },
             SortOrder::Descending,
-            opt.max_results.unwrap_or(100),
-            Some(opt.start as u32),
-            c.filter_by_main_category,
+            c.page_size.unwrap_or(opt.max_results.unwrap_or(100)),
+            opt.start,
+            // メインカテゴリでの絞り込みはPaperFilterで行う
+            false,
         );
+        if let Some(n) = c.max_total_results {
+            arxiv_api_interface.max_total_results(n);
+        }
+        if let Some(url) = c.api_url.as_ref().or(config.api_url.as_ref()) {
+            arxiv_api_interface.api_url(url);
+        }
 
-        let arxiv_api = ArxivAPIUseCase::new(arxiv_api_interface);
+        if let Some(authors) = &c.follow_authors {
+            arxiv_api_interface.follow_authors(authors);
+        }
+        if let Some(q) = &c.query {
+            match query::parse(q) {
+                Ok(q) => arxiv_api_interface.expression(q),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+        }
+        let search_query = match arxiv_api_interface.search_query() {
+            Ok(q) => q,
+            Err(e) => {
+                eprintln!("Invalid subscription {:?}: {}", c.name, e);
+                continue;
+            }
+        };
+        let subscription = c.name.clone().unwrap_or(search_query);
+        // 保存済みの論文での表記(複合姓など)でも検索する。
+        // 保存済みの論文によって購読のキーが変わらないように、キーを決めた後に追加する
+        if let Some(authors) = &c.follow_authors {
+            match ArxivPaperUseCase::new(&*repositories.arxiv_paper).find_author_names(authors) {
+                Ok(stored) if !stored.is_empty() => {
+                    let mut names = authors.clone();
+                    names.extend(stored);
+                    arxiv_api_interface.follow_authors(&names);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to find stored authors for {}: {:?}", &subscription, e),
+            }
+        }
+        // 購読のキーが実行日時で変わらないように、日付の範囲はキーを決めた後に設定する
+        let now = Utc::now().naive_utc();
+        let window = match (opt.from, c.lookback_days) {
+            // --toの日付はその日の終わりまで含める
+            (Some(from), _) => Some((from, opt.to.map(|t| t + Duration::days(1) - Duration::minutes(1)).unwrap_or(now))),
+            (None, Some(days)) => Some((now - Duration::days(days), now)),
+            (None, None) => None,
+        };
+        if let Some((from, to)) = window {
+            arxiv_api_interface.submitted_between(from, to);
+        }
+        let mut rules = match source {
+            Source::Api => vec![],
+            _ => word_rules(c),
+        };
+        match c.filters.as_ref().map(|f| filter_rules(f)).transpose() {
+            Ok(filters) => rules.extend(filters.unwrap_or_default()),
+            Err(e) => {
+                eprintln!("Invalid filters in {}: {}", &subscription, e);
+                continue;
+            }
+        }
+        let category_rule = category_rule(c, &source);
+        let filter = match rules.is_empty() && category_rule.is_none() && !c.require_code && c.min_pages.is_none() {
+            true => None,
+            false => match PaperFilter::new(&rules) {
+                Ok(filter) => Some(match category_rule {
+                    Some(rule) => filter.with_categories(rule),
+                    None => filter,
+                }.with_require_code(c.require_code).with_min_pages(c.min_pages)),
+                Err(e) => {
+                    eprintln!("Invalid filters in {}: {}", &subscription, e);
+                    continue;
+                }
+            },
+        };
+        let subscription_state = SubscriptionStateUseCase::new(&*repositories.subscription_state);
+        let since = if opt.full_resync || opt.from.is_some() {
+            None
+        } else if opt.since.is_some() {
+            opt.since
+        } else {
+            subscription_state.find_last_updated(&subscription)?
+        };
+        arxiv_api_interface
+            .since(since)
+            .retry_policy(retry_policy(&config.retry));
+        if let Some(dir) = &opt.record {
+            arxiv_api_interface.record(dir);
+        }
+        if let Some(dir) = &opt.replay {
+            arxiv_api_interface.replay(dir);
+        }
 
-        let papers = arxiv_api.query().unwrap();
+        let papers = match source {
+            Source::Api => ArxivAPIUseCase::new(&arxiv_api_interface).query(),
+            Source::Rss => query_rss(config, c, opt),
+            Source::Oai => {
+                // 前回の取得位置も期間の指定もなければ直近1日分を取得する
+                let from = window.map(|w| w.0).or(since).unwrap_or(now - Duration::days(1));
+                query_oai(config, c, opt, from.date(), opt.to.map(|t| t.date()))
+            }
+        };
+        let papers = match papers {
+            Ok(papers) => papers,
+            Err(e) => {
+                // 一つの購読の失敗で残りの購読が処理されなくなるのを防ぐ
+                eprintln!("Failed to query arXiv for {}: {:?}", &subscription, e);
+                thread::sleep(time::Duration::from_millis(3000));
+                continue;
+            }
+        };
 
         match papers.len() {
             0 => println!("No paper found"),
@@ -105,65 +705,65 @@ fn main() {
             _ => println!("{} papers found", papers.len()),
         }
 
+        // フィルタで落とした論文も取得済みとして扱う
+        let newest_updated = if source == Source::Api && arxiv_api_interface.is_truncated() {
+            // max_total_resultsで打ち切った場合、取得していない論文は今回の論文より古く前回の位置より新しいので、
+            // 取得位置を進めると次回以降も取得されなくなる
+            eprintln!("Reached max_total_results for {}; the fetch position is not advanced", &subscription);
+            None
+        } else {
+            papers.iter().map(|p| p.updated).max()
+        };
+        let papers = match &filter {
+            Some(filter) => {
+                let papers = filter.apply(papers, opt.debug_filter);
+                println!("{} papers left after filtering", papers.len());
+                papers
+            }
+            None => papers,
+        };
+
         if opt.save {
-            let papers = papers.iter().map(|p| NewPaper::from(p.clone())).collect();
-
-            let arxiv_paper_repository = ArxivPaperRepository::new();
-            let slack_notification_repository = SlackNotificationRepository::new();
-            let arxiv_paper = ArxivPaperUseCase::new(arxiv_paper_repository);
-            let slack_notification = SlackNotificationUseCase::new(slack_notification_repository);
-
-            let n_papers = arxiv_paper.save(&papers).unwrap();
-            match n_papers {
-                0 => println!("No paper saved"),
-                1 => println!("1 paper saved"),
-                _ => println!("{} papers saved", &n_papers),
+            let papers = new_papers(&papers, &venues);
+            // 保存に失敗した場合は取得位置を進めず、次回取得し直す
+            if let Err(e) = save_papers(repositories, &papers, Some(&c.slack), Some(&subscription), &revision_policy) {
+                eprintln!("Failed to save papers for {}: {:?}", &subscription, e);
+                continue;
             }
 
-            let paper_urls = extract_paper_urls(&papers);
-            let papers = arxiv_paper.find_by_urls(&paper_urls).unwrap();
-
-            if !papers.is_empty() {
-                let notifications = papers
-                    .iter()
-                    .map(|p| NewSlackNotification {
-                        paper_id: p.id.clone(),
-                        slack_url: c.slack.clone(),
-                        updated: p.updated,
-                    })
-                    .collect::<Vec<NewSlackNotification>>();
-                slack_notification
-                    .enqueue_slack_notifications(&notifications)
-                    .unwrap();
+            if let Some(last_updated) = newest_updated {
+                // 論文は保存済みなので、取得位置を更新できなくても通知は続ける
+                if let Err(e) = subscription_state.update_last_updated(&subscription, last_updated) {
+                    eprintln!("Failed to update the fetch position for {}: {:?}", &subscription, e);
+                }
             }
         }
 
         if opt.slack {
-            let slack_notification_repository = SlackNotificationRepository::new();
-            let slack_notification = SlackNotificationUseCase::new(slack_notification_repository);
+            let slack_notification = SlackNotificationUseCase::new(&*repositories.slack_notification);
+            // --ephemeralではslackに送らず、送るはずだったメッセージを表示する
+            let slack_api_interface: Box<dyn SlackAPITrait> = match opt.ephemeral {
+                true => Box::new(InMemorySlackAPI::new(&c.slack)),
+                false => Box::new(SlackAPI::new(&c.slack)),
+            };
+            let slack_api = SlackAPIUseCase::new(&*slack_api_interface);
 
-            let papers = slack_notification.find_not_send(&c.slack).unwrap();
-            if papers.is_empty() {
+            let messages = send_queued(&slack_notification, &slack_api, &c.slack, &c.star_keywords, &c.follow_authors, opt.send)?;
+            if messages.is_empty() {
                 println!("All papers have been sent to slack");
-            } else {
-                let slack_api_interface = SlackAPI::new(&c.slack);
-                let slack_api = SlackAPIUseCase::new(slack_api_interface);
-
-                //一つずつsendしたほうが伝わる
-                let messages = slack_api.build_messages(&papers, &c.star_keywords);
-                if opt.send {
-                    for m in &messages {
-                        slack_api.send(&m.1);
-                        slack_notification.mark_as_send(&c.slack, &m.0.id).unwrap();
-                    }
-                } else {
-                    for m in &messages {
-                        println!("{:?}", &m.1);
-                    }
-                    println!("{} messages to send", messages.len());
+            } else if !opt.send || opt.ephemeral {
+                for m in &messages {
+                    println!("{:?}", &m.1);
+                }
+                match opt.send {
+                    true => println!("{} messages recorded (--ephemeral does not send to slack)", messages.len()),
+                    false => println!("{} messages to send", messages.len()),
                 }
             }
         }
-        thread::sleep(time::Duration::from_millis(3000))  // for arxiv api limit
+        if opt.replay.is_none() {
+            thread::sleep(time::Duration::from_millis(3000))  // for arxiv api limit
+        }
     }
+    Ok(())
 }
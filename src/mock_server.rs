@@ -0,0 +1,67 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Response, Server};
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.to_string(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+// 受け取ったリクエストに順番にresponsesを返すテスト用のHTTPサーバー
+// responsesを使い切った後は最後のレスポンスを返し続ける
+pub struct MockServer {
+    server: Arc<Server>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let (s, r) = (server.clone(), requests.clone());
+        thread::spawn(move || {
+            for (i, request) in s.incoming_requests().enumerate() {
+                r.lock().unwrap().push(request.url().to_string());
+                let mock = &responses[i.min(responses.len() - 1)];
+                let mut response = Response::from_string(mock.body.clone()).with_status_code(mock.status);
+                for (name, value) in &mock.headers {
+                    response = response.with_header(
+                        Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap());
+                }
+                let _ = request.respond(response);
+            }
+        });
+        Self { server, requests }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.server.server_addr())
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+    }
+}
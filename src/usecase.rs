@@ -1,4 +1,9 @@
 pub mod arxiv_paper;
 pub mod slack_notifications;
 pub mod arxiv_api;
-pub mod slack_api;
\ No newline at end of file
+pub mod slack_api;
+pub mod paper_filter;
+pub mod pipeline;
+pub mod oai_harvest;
+pub mod subscription_state;
+pub mod revision_policy;
\ No newline at end of file
@@ -1,9 +1,14 @@
-use crate::domain::arxiv_paper::{Paper, PaperId, NewPaper, ArxivPaperRepositoryTrait};
+use crate::domain::arxiv_paper::{Paper, PaperFile, PaperId, NewPaper, ArxivPaperRepositoryTrait};
 
 pub trait ArxivPaperUseCaseTrait {
     fn find_by_id(&self, id: PaperId) -> anyhow::Result<Option<Paper>>;
     fn find_by_urls(&self, urls: &Vec<String>) -> anyhow::Result<Vec<Paper>>;
+    fn find_by_ids(&self, ids: &[PaperId]) -> anyhow::Result<Vec<Paper>>;
+    fn find_by_arxiv_ids(&self, arxiv_ids: &[String]) -> anyhow::Result<Vec<Paper>>;
     fn save(&self, papers: &Vec<NewPaper>) -> anyhow::Result<usize>;
+    fn find_files(&self, ids: &[PaperId]) -> anyhow::Result<Vec<PaperFile>>;
+    fn find_author_names(&self, follow_authors: &[String]) -> anyhow::Result<Vec<String>>;
+    fn download(&self, papers: &Vec<Paper>) -> anyhow::Result<Vec<PaperFile>>;
 }
 
 #[derive(Clone)]
@@ -32,9 +37,24 @@ impl<A> ArxivPaperUseCaseTrait for ArxivPaperUseCase<A>
     fn find_by_urls(&self, urls: &Vec<String>) -> anyhow::Result<Vec<Paper>> {
         self.arxiv_paper_repository.find_by_urls(urls)
     }
+    fn find_by_ids(&self, ids: &[PaperId]) -> anyhow::Result<Vec<Paper>> {
+        self.arxiv_paper_repository.find_by_ids(ids)
+    }
+    fn find_by_arxiv_ids(&self, arxiv_ids: &[String]) -> anyhow::Result<Vec<Paper>> {
+        self.arxiv_paper_repository.find_by_arxiv_ids(arxiv_ids)
+    }
     fn save(&self, papers: &Vec<NewPaper>) -> anyhow::Result<usize> {
         self.arxiv_paper_repository.save(papers)
     }
+    fn find_files(&self, ids: &[PaperId]) -> anyhow::Result<Vec<PaperFile>> {
+        self.arxiv_paper_repository.find_files(ids)
+    }
+    fn find_author_names(&self, follow_authors: &[String]) -> anyhow::Result<Vec<String>> {
+        self.arxiv_paper_repository.find_author_names(follow_authors)
+    }
+    fn download(&self, papers: &Vec<Paper>) -> anyhow::Result<Vec<PaperFile>> {
+        self.arxiv_paper_repository.download(papers)
+    }
 }
 
 
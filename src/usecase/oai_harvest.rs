@@ -0,0 +1,38 @@
+use crate::domain::oai_harvest::{OaiHarvest, OaiHarvestRepositoryTrait};
+
+pub trait OaiHarvestUseCaseTrait {
+    fn find_resumption_token(&self, harvest: &str) -> anyhow::Result<Option<String>>;
+    fn save_resumption_token(&self, harvest: &str, resumption_token: Option<&str>) -> anyhow::Result<usize>;
+}
+
+#[derive(Clone)]
+pub struct OaiHarvestUseCase<H>
+    where H: OaiHarvestRepositoryTrait
+{
+    pub oai_harvest_repository: H,
+}
+
+impl<H> OaiHarvestUseCase<H>
+    where H: OaiHarvestRepositoryTrait
+{
+    pub fn new(oai_harvest_repository: H) -> Self {
+        Self {
+            oai_harvest_repository
+        }
+    }
+}
+
+impl<H> OaiHarvestUseCaseTrait for OaiHarvestUseCase<H>
+    where H: OaiHarvestRepositoryTrait
+{
+    fn find_resumption_token(&self, harvest: &str) -> anyhow::Result<Option<String>> {
+        let state = self.oai_harvest_repository.find(harvest)?;
+        Ok(state.and_then(|s| s.resumption_token))
+    }
+    fn save_resumption_token(&self, harvest: &str, resumption_token: Option<&str>) -> anyhow::Result<usize> {
+        self.oai_harvest_repository.save(&OaiHarvest {
+            harvest: harvest.to_string(),
+            resumption_token: resumption_token.map(|t| t.to_string()),
+        })
+    }
+}
@@ -0,0 +1,306 @@
+use regex::{Regex, RegexBuilder};
+use crate::domain::arxiv_api::Paper;
+use crate::domain::latex::to_unicode;
+use crate::domain::paper_counts::PaperCounts;
+use crate::domain::paper_link::{extract_links, LinkKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterField {
+    Title,
+    Abstract,
+    Comment,
+    Authors,
+    Categories,
+    JournalRef,
+    Doi,
+    Affiliations,
+    Classes,
+}
+
+impl FilterField {
+    pub fn parse(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "title" => Ok(FilterField::Title),
+            "abstract" => Ok(FilterField::Abstract),
+            "comment" => Ok(FilterField::Comment),
+            "authors" => Ok(FilterField::Authors),
+            "categories" => Ok(FilterField::Categories),
+            "journal_ref" => Ok(FilterField::JournalRef),
+            "doi" => Ok(FilterField::Doi),
+            "affiliations" => Ok(FilterField::Affiliations),
+            "classes" => Ok(FilterField::Classes),
+            _ => anyhow::bail!(
+                "Unknown filter field `{}` (use one of title, abstract, comment, authors, categories, journal_ref, doi, affiliations, classes)",
+                name
+            ),
+        }
+    }
+
+    fn text(&self, paper: &Paper) -> String {
+        match self {
+            // LaTeXはUnicodeにしてから検索する
+            FilterField::Title => to_unicode(&paper.title),
+            FilterField::Abstract => to_unicode(&paper.summary),
+            FilterField::Comment => to_unicode(&paper.comment),
+            FilterField::Authors => paper.authors.join(", "),
+            FilterField::Categories => paper.categories.join(" "),
+            FilterField::JournalRef => paper.journal_ref.clone().unwrap_or_default(),
+            FilterField::Doi => paper.doi.clone().unwrap_or_default(),
+            FilterField::Affiliations => paper.affiliations.iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<&str>>()
+                .join("; "),
+            FilterField::Classes => paper.classes.join("; "),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            FilterField::Title => "title",
+            FilterField::Abstract => "abstract",
+            FilterField::Comment => "comment",
+            FilterField::Authors => "authors",
+            FilterField::Categories => "categories",
+            FilterField::JournalRef => "journal_ref",
+            FilterField::Doi => "doi",
+            FilterField::Affiliations => "affiliations",
+            FilterField::Classes => "classes",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilterRule {
+    pub field: FilterField,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    // falseのときは大文字小文字を無視した単語単位の一致、trueのときは正規表現
+    pub regex: bool,
+    // trueのときは値が空の論文を落とす（journal_refがある論文だけ残す、など）
+    pub required: bool,
+}
+
+// メインカテゴリがprimaryのどれか、またはcross_listedのどれかにクロスリストされている論文を残す
+#[derive(Debug, Clone)]
+pub struct CategoryRule {
+    pub primary: Vec<String>,
+    pub cross_listed: Vec<String>,
+}
+
+impl CategoryRule {
+    fn check(&self, paper: &Paper) -> Result<String, String> {
+        if self.primary.contains(&paper.category) {
+            return Ok(format!("primary category is {}", paper.category));
+        }
+        match paper.categories.iter().find(|c| self.cross_listed.contains(c)) {
+            Some(c) => Ok(format!("cross-listed in {}", c)),
+            None => Err(format!("primary category {} is not wanted", paper.category)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    field: FilterField,
+    required: bool,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+#[derive(Debug)]
+pub struct PaperFilter {
+    rules: Vec<CompiledRule>,
+    category_rule: Option<CategoryRule>,
+    // trueのときはコードのURLが書かれている論文だけ残す
+    require_code: bool,
+    // コメントに書かれたページ数がこれより少ない論文を落とす（ページ数が分からない論文は残す）
+    min_pages: Option<u32>,
+}
+
+fn compile(pattern: &str, regex: bool) -> anyhow::Result<Regex> {
+    let pattern = match regex {
+        true => pattern.to_string(),
+        false => {
+            // \bは単語の文字の隣でしか一致しないので、C++や.NETのように記号で始まる・終わる側にはつけない
+            let boundary = |c: Option<char>| match c {
+                Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+                _ => "",
+            };
+            format!("{}{}{}", boundary(pattern.chars().next()), regex::escape(pattern), boundary(pattern.chars().last()))
+        }
+    };
+    Ok(RegexBuilder::new(&pattern).case_insensitive(true).build()?)
+}
+
+impl PaperFilter {
+    pub fn new(rules: &[FilterRule]) -> anyhow::Result<Self> {
+        let rules = rules.iter().map(|r| Ok(CompiledRule {
+            field: r.field.clone(),
+            required: r.required,
+            include: r.include.iter().map(|p| compile(p, r.regex)).collect::<anyhow::Result<_>>()?,
+            exclude: r.exclude.iter().map(|p| compile(p, r.regex)).collect::<anyhow::Result<_>>()?,
+        })).collect::<anyhow::Result<_>>()?;
+        Ok(Self { rules, category_rule: None, require_code: false, min_pages: None })
+    }
+
+    pub fn with_categories(mut self, category_rule: CategoryRule) -> Self {
+        self.category_rule = Some(category_rule);
+        self
+    }
+
+    pub fn with_require_code(mut self, require_code: bool) -> Self {
+        self.require_code = require_code;
+        self
+    }
+
+    pub fn with_min_pages(mut self, min_pages: Option<u32>) -> Self {
+        self.min_pages = min_pages;
+        self
+    }
+
+    // 残すならOk、落とすならErrでその理由を返す
+    pub fn check(&self, paper: &Paper) -> Result<Vec<String>, String> {
+        let mut reasons = vec![];
+        if let Some(rule) = &self.category_rule {
+            reasons.push(rule.check(paper)?);
+        }
+        if self.require_code {
+            let links = extract_links(&format!("{}\n{}", paper.summary, paper.comment));
+            match links.iter().find(|l| l.kind == LinkKind::Code) {
+                Some(link) => reasons.push(format!("code at {}", link.url)),
+                None => return Err("no code link".to_string()),
+            }
+        }
+        if let Some(min_pages) = self.min_pages {
+            match PaperCounts::parse(&paper.comment).pages {
+                Some(pages) if pages < min_pages => return Err(format!("{} pages < min_pages {}", pages, min_pages)),
+                Some(pages) => reasons.push(format!("{} pages", pages)),
+                None => {}
+            }
+        }
+        for rule in &self.rules {
+            let text = rule.field.text(paper);
+            if rule.required && text.trim().is_empty() {
+                return Err(format!("{} is empty", rule.field.name()));
+            }
+            if let Some(re) = rule.exclude.iter().find(|re| re.is_match(&text)) {
+                return Err(format!("{} matched exclude /{}/", rule.field.name(), re.as_str()));
+            }
+            if rule.include.is_empty() {
+                continue;
+            }
+            match rule.include.iter().find(|re| re.is_match(&text)) {
+                Some(re) => reasons.push(format!("{} matched /{}/", rule.field.name(), re.as_str())),
+                None => return Err(format!("{} matched none of the include patterns", rule.field.name())),
+            }
+        }
+        Ok(reasons)
+    }
+
+    pub fn apply(&self, papers: Vec<Paper>, debug: bool) -> Vec<Paper> {
+        papers.into_iter().filter(|p| {
+            let result = self.check(p);
+            if debug {
+                match &result {
+                    Ok(reasons) => println!("kept    {} ({})", p.title, reasons.join(", ")),
+                    Err(reason) => println!("dropped {} ({})", p.title, reason),
+                }
+            }
+            result.is_ok()
+        }).collect()
+    }
+}
+
+#[test]
+fn test_paper_filter() {
+    let paper = |title: &str| Paper {
+        title: title.to_string(),
+        url: "".to_string(),
+        pdf_url: "".to_string(),
+        authors: vec!["Taro Yamada".to_string()],
+        category: "cs.CV".to_string(),
+        categories: vec!["cs.CV".to_string(), "cs.LG".to_string()],
+        summary: "".to_string(),
+        comment: "".to_string(),
+        is_accepted: false,
+        journal_ref: None,
+        doi: None,
+        affiliations: vec![],
+        classes: vec![],
+        updated: chrono::NaiveDateTime::from_timestamp(0, 0),
+        published: chrono::NaiveDateTime::from_timestamp(0, 0),
+    };
+    let filter = PaperFilter::new(&[
+        FilterRule {
+            field: FilterField::Title,
+            include: vec!["face".to_string()],
+            exclude: vec![],
+            regex: false,
+            required: false,
+        },
+        FilterRule {
+            field: FilterField::Authors,
+            include: vec![],
+            exclude: vec![r"^Jane\b".to_string()],
+            regex: true,
+            required: false,
+        },
+    ]).unwrap();
+    assert!(filter.check(&paper("Face Recognition in the Wild")).is_ok());
+    assert!(filter.check(&paper("Surface Reconstruction")).is_err());
+    let mut jane = paper("Face Parsing");
+    jane.authors = vec!["Jane Doe".to_string()];
+    assert!(filter.check(&jane).is_err());
+
+    let filter = PaperFilter::new(&[FilterRule {
+        field: FilterField::Title,
+        include: vec!["C++".to_string(), ".NET".to_string()],
+        exclude: vec![],
+        regex: false,
+        required: false,
+    }]).unwrap();
+    assert!(filter.check(&paper("Fast Convolutions in C++")).is_ok());
+    assert!(filter.check(&paper("C++ Templates for Tensors")).is_ok());
+    assert!(filter.check(&paper("Porting Models to .NET")).is_ok());
+    assert!(filter.check(&paper("C Programming for GPUs")).is_err());
+    assert!(filter.check(&paper("ASP.NETwork Analysis")).is_err());
+
+    let filter = PaperFilter::new(&[FilterRule {
+        field: FilterField::JournalRef,
+        include: vec![],
+        exclude: vec![],
+        regex: false,
+        required: true,
+    }]).unwrap();
+    assert!(filter.check(&paper("Face Parsing")).is_err());
+    let mut published = paper("Face Parsing");
+    published.journal_ref = Some("CVPR 2021".to_string());
+    assert!(filter.check(&published).is_ok());
+
+    let filter = PaperFilter::new(&[]).unwrap().with_categories(CategoryRule {
+        primary: vec!["eess.IV".to_string()],
+        cross_listed: vec!["cs.LG".to_string()],
+    });
+    assert!(filter.check(&paper("Face Parsing")).is_ok());
+    let mut primary = paper("Face Parsing");
+    primary.category = "eess.IV".to_string();
+    primary.categories = vec!["eess.IV".to_string()];
+    assert!(filter.check(&primary).is_ok());
+    let mut other = paper("Face Parsing");
+    other.categories = vec!["cs.CV".to_string(), "cs.RO".to_string()];
+    assert!(filter.check(&other).is_err());
+
+    let filter = PaperFilter::new(&[]).unwrap().with_require_code(true);
+    assert!(filter.check(&paper("Face Parsing")).is_err());
+    let mut with_code = paper("Face Parsing");
+    with_code.comment = "Code is available at https://github.com/foo/face-parsing".to_string();
+    assert!(filter.check(&with_code).is_ok());
+
+    let filter = PaperFilter::new(&[]).unwrap().with_min_pages(Some(6));
+    assert!(filter.check(&paper("Face Parsing")).is_ok());
+    let mut short = paper("Face Parsing");
+    short.comment = "4 pages, 2 figures".to_string();
+    assert!(filter.check(&short).is_err());
+    short.comment = "8+2 pages, 5 figures".to_string();
+    assert!(filter.check(&short).is_ok());
+}
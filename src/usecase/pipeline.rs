@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use crate::domain::arxiv_paper::{NewPaper, Paper, PaperId};
+use crate::domain::slack_notification::NewSlackNotification;
+use crate::usecase::arxiv_paper::{extract_paper_urls, ArxivPaperUseCaseTrait};
+use crate::usecase::revision_policy::RevisionPolicy;
+use crate::usecase::slack_api::SlackAPIUseCaseTrait;
+use crate::usecase::slack_notifications::SlackNotificationUseCaseTrait;
+use crate::usecase::subscription_state::SubscriptionStateUseCaseTrait;
+
+// 取得した論文を保存し、slack_urlがあれば通知すべき論文をslackへの送信キューに入れる。保存した論文の数を返す
+pub fn save_and_enqueue(arxiv_paper: &impl ArxivPaperUseCaseTrait,
+                        slack_notification: &impl SlackNotificationUseCaseTrait,
+                        papers: &Vec<NewPaper>,
+                        slack_url: Option<&str>,
+                        revision_policy: &RevisionPolicy,
+) -> anyhow::Result<usize> {
+    let n_papers = arxiv_paper.save(papers)?;
+    let slack_url = match slack_url {
+        Some(url) => url,
+        None => return Ok(n_papers),
+    };
+    let paper_urls = extract_paper_urls(papers);
+    let papers = arxiv_paper.find_by_urls(&paper_urls)?;
+    let paper_ids: Vec<PaperId> = papers.iter().map(|p| p.id.clone()).collect();
+    let notified = slack_notification.find_notified(slack_url, &paper_ids)?;
+    let papers: Vec<_> = papers.into_iter()
+        .filter(|p| revision_policy.should_notify(p, notified.contains(&p.id)))
+        .collect();
+
+    if !papers.is_empty() {
+        let notifications = papers
+            .iter()
+            .map(|p| NewSlackNotification {
+                paper_id: p.id.clone(),
+                slack_url: slack_url.to_string(),
+                updated: p.updated,
+            })
+            .collect::<Vec<NewSlackNotification>>();
+        slack_notification.enqueue_slack_notifications(&notifications)?;
+    }
+    Ok(n_papers)
+}
+
+// 保存した論文を購読の論文として記録する。slackの送信キューはwebhookごとなので、同じwebhookを使う購読はここで区別する
+// 保存済みの行の方が新しいバージョンの場合もあるので、バージョンを除いたarXiv IDで探す
+pub fn record_subscription(arxiv_paper: &impl ArxivPaperUseCaseTrait,
+                           subscription_state: &impl SubscriptionStateUseCaseTrait,
+                           subscription: &str,
+                           papers: &[NewPaper],
+) -> anyhow::Result<usize> {
+    let arxiv_ids: Vec<String> = papers.iter()
+        .filter_map(|p| p.arxiv_id.as_ref().map(|id| id.id.clone()))
+        .collect();
+    let paper_ids: Vec<PaperId> = arxiv_paper.find_by_arxiv_ids(&arxiv_ids)?
+        .into_iter()
+        .map(|p| p.id)
+        .collect();
+    subscription_state.add_papers(subscription, &paper_ids)
+}
+
+// record_subscriptionで購読の論文として記録した論文を記録した順に返す
+// subscription_papersを追加するマイグレーションより前に保存した論文は記録されていないので含まれない
+pub fn find_subscription_papers(arxiv_paper: &impl ArxivPaperUseCaseTrait,
+                                subscription_state: &impl SubscriptionStateUseCaseTrait,
+                                subscription: &str,
+) -> anyhow::Result<Vec<Paper>> {
+    let paper_ids = subscription_state.find_paper_ids(subscription)?;
+    let order: HashMap<&PaperId, usize> = paper_ids.iter().enumerate().map(|(i, id)| (id, i)).collect();
+    let mut papers = arxiv_paper.find_by_ids(&paper_ids)?;
+    papers.sort_by_key(|p| order[&p.id]);
+    Ok(papers)
+}
+
+// 送信キューにある論文のメッセージを作る。sendの場合は一つずつ送って送信済みにする
+pub fn send_queued(slack_notification: &impl SlackNotificationUseCaseTrait,
+                   slack_api: &impl SlackAPIUseCaseTrait,
+                   slack_url: &str,
+                   star_keywords: &Option<Vec<String>>,
+                   follow_authors: &Option<Vec<String>>,
+                   send: bool,
+) -> anyhow::Result<Vec<(Paper, String)>> {
+    let papers = slack_notification.find_not_send(slack_url)?;
+    if papers.is_empty() {
+        return Ok(vec![]);
+    }
+    //一つずつsendしたほうが伝わる
+    let messages = slack_api.build_messages(&papers, star_keywords, follow_authors);
+    if send {
+        for m in &messages {
+            slack_api.send(&m.1);
+            slack_notification.mark_as_send(slack_url, &m.0.id)?;
+        }
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+struct FakeArxivAPI {
+    papers: Vec<crate::domain::arxiv_api::Paper>,
+}
+
+#[cfg(test)]
+impl crate::domain::arxiv_api::ArxivAPITrait for FakeArxivAPI {
+    fn query(&self) -> anyhow::Result<Vec<crate::domain::arxiv_api::Paper>> {
+        Ok(self.papers.clone())
+    }
+}
+
+#[cfg(test)]
+fn fetch(ids: &[(&str, &str)]) -> Vec<NewPaper> {
+    use crate::domain::arxiv_api::Paper as ApiPaper;
+    use crate::domain::arxiv_id::ArxivId;
+    use crate::usecase::arxiv_api::{ArxivAPIUseCase, ArxivAPIUseCaseTrait};
+    let date = chrono::NaiveDate::from_ymd(2021, 4, 1).and_hms(0, 0, 0);
+    // 新しいバージョンほど更新日時を後にする
+    let updated = |id: &str| date + chrono::Duration::days(ArxivId::parse(id).unwrap().version.unwrap_or(1) as i64);
+    let papers = ids.iter().map(|(id, title)| ApiPaper {
+        title: title.to_string(),
+        url: format!("http://arxiv.org/abs/{}", id),
+        pdf_url: format!("http://arxiv.org/pdf/{}", id),
+        authors: vec!["Taro Yamada".to_string()],
+        category: "cs.CV".to_string(),
+        categories: vec!["cs.CV".to_string()],
+        summary: "We parse faces.".to_string(),
+        comment: "".to_string(),
+        is_accepted: false,
+        journal_ref: None,
+        doi: None,
+        affiliations: vec![],
+        classes: vec![],
+        updated: updated(id),
+        published: date,
+    }).collect();
+    let arxiv_api = ArxivAPIUseCase::new(FakeArxivAPI { papers });
+    arxiv_api.query().unwrap().into_iter().map(NewPaper::from).collect()
+}
+
+#[cfg(test)]
+const TEST_SLACK_URL: &str = "https://hooks.slack.com/services/test";
+
+// 一つのインメモリのストアを共有するユースケース
+#[cfg(test)]
+struct TestUseCases {
+    arxiv_paper: crate::usecase::arxiv_paper::ArxivPaperUseCase<crate::infrastructure::memory::InMemoryArxivPaperRepository>,
+    slack_notification: crate::usecase::slack_notifications::SlackNotificationUseCase<crate::infrastructure::memory::InMemorySlackNotificationRepository>,
+    subscription_state: crate::usecase::subscription_state::SubscriptionStateUseCase<crate::infrastructure::memory::InMemorySubscriptionStateRepository>,
+    slack_api: crate::usecase::slack_api::SlackAPIUseCase<crate::infrastructure::memory::InMemorySlackAPI>,
+}
+
+#[cfg(test)]
+fn test_usecases() -> TestUseCases {
+    use crate::infrastructure::memory::{InMemoryArxivPaperRepository, InMemorySlackAPI, InMemorySlackNotificationRepository, InMemoryStore, InMemorySubscriptionStateRepository};
+    use crate::usecase::arxiv_paper::ArxivPaperUseCase;
+    use crate::usecase::slack_api::SlackAPIUseCase;
+    use crate::usecase::slack_notifications::SlackNotificationUseCase;
+    use crate::usecase::subscription_state::SubscriptionStateUseCase;
+    let store = InMemoryStore::default();
+    TestUseCases {
+        arxiv_paper: ArxivPaperUseCase::new(InMemoryArxivPaperRepository::new(store.clone())),
+        slack_notification: SlackNotificationUseCase::new(InMemorySlackNotificationRepository::new(store.clone())),
+        subscription_state: SubscriptionStateUseCase::new(InMemorySubscriptionStateRepository::new(store)),
+        slack_api: SlackAPIUseCase::new(InMemorySlackAPI::new(TEST_SLACK_URL)),
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let slack_url = TEST_SLACK_URL;
+    let TestUseCases { arxiv_paper, slack_notification, slack_api, .. } = test_usecases();
+    let slack = &slack_api.slack_api;
+    let run = |papers: &Vec<NewPaper>, policy: &RevisionPolicy| {
+        let n = save_and_enqueue(&arxiv_paper, &slack_notification, papers, Some(slack_url), policy).unwrap();
+        let messages = send_queued(&slack_notification, &slack_api, slack_url, &None, &None, true).unwrap();
+        (n, messages.len())
+    };
+
+    let papers = fetch(&[("2104.00001v1", "Face Parsing"), ("2104.00002v1", "Hair Parsing")]);
+    assert_eq!(run(&papers, &RevisionPolicy::AllVersions), (2, 2));
+    assert_eq!(slack.messages().len(), 2);
+    assert!(slack.messages()[0].contains("Face Parsing"));
+    // 保存済みで通知済みの論文は送らない
+    assert_eq!(run(&papers, &RevisionPolicy::AllVersions), (0, 0));
+
+    // 新しいバージョンはrevision_policyに従って通知する
+    let v2 = fetch(&[("2104.00001v2", "Face Parsing v2")]);
+    assert_eq!(run(&v2, &RevisionPolicy::NewOnly), (1, 0));
+    let v3 = fetch(&[("2104.00001v3", "Face Parsing v3")]);
+    assert_eq!(run(&v3, &RevisionPolicy::AllVersions), (1, 1));
+    assert!(slack.messages()[2].contains("Face Parsing v3"));
+    let paper = arxiv_paper.find_by_urls(&vec!["http://arxiv.org/abs/2104.00001v3".to_string()]).unwrap();
+    assert_eq!(paper[0].versions.iter().map(|v| v.version).collect::<Vec<u32>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_pipeline_without_send() {
+    let slack_url = TEST_SLACK_URL;
+    let TestUseCases { arxiv_paper, slack_notification, slack_api, .. } = test_usecases();
+    let slack = &slack_api.slack_api;
+
+    // slack_urlがなければ保存するだけ
+    let papers = fetch(&[("2104.00001v1", "Face Parsing")]);
+    assert_eq!(save_and_enqueue(&arxiv_paper, &slack_notification, &papers, None, &RevisionPolicy::AllVersions).unwrap(), 1);
+    assert!(send_queued(&slack_notification, &slack_api, slack_url, &None, &None, true).unwrap().is_empty());
+
+    let papers = fetch(&[("2104.00001v1", "Face Parsing"), ("2104.00002v1", "Hair Parsing")]);
+    assert_eq!(save_and_enqueue(&arxiv_paper, &slack_notification, &papers, Some(slack_url), &RevisionPolicy::AllVersions).unwrap(), 1);
+    // sendしなければメッセージを作るだけでキューに残す
+    assert_eq!(send_queued(&slack_notification, &slack_api, slack_url, &None, &None, false).unwrap().len(), 2);
+    assert!(slack.messages().is_empty());
+    assert_eq!(send_queued(&slack_notification, &slack_api, slack_url, &None, &None, true).unwrap().len(), 2);
+    assert_eq!(slack.messages().len(), 2);
+}
+
+#[test]
+fn test_subscriptions_sharing_webhook() {
+    let slack_url = TEST_SLACK_URL;
+    let TestUseCases { arxiv_paper, slack_notification, subscription_state, .. } = test_usecases();
+    let run = |subscription: &str, papers: &Vec<NewPaper>| {
+        save_and_enqueue(&arxiv_paper, &slack_notification, papers, Some(slack_url), &RevisionPolicy::AllVersions).unwrap();
+        record_subscription(&arxiv_paper, &subscription_state, subscription, papers).unwrap();
+    };
+
+    run("face", &fetch(&[("2104.00001v1", "Face Parsing")]));
+    run("hair", &fetch(&[("2104.00002v1", "Hair Parsing")]));
+    // 他の購読で通知済みの論文も、その購読の論文として記録する
+    run("hair", &fetch(&[("2104.00001v1", "Face Parsing")]));
+    let titles = |subscription: &str| find_subscription_papers(&arxiv_paper, &subscription_state, subscription).unwrap()
+        .into_iter().map(|p| p.title).collect::<Vec<String>>();
+    assert_eq!(titles("face"), vec!["Face Parsing"]);
+    assert_eq!(titles("hair"), vec!["Hair Parsing", "Face Parsing"]);
+    // 保存済みの行より古いバージョンを取得した場合も記録する
+    run("face", &fetch(&[("2104.00003v2", "Skin Parsing v2")]));
+    run("hair", &fetch(&[("2104.00003v1", "Skin Parsing")]));
+    assert_eq!(titles("hair"), vec!["Hair Parsing", "Face Parsing", "Skin Parsing v2"]);
+    // slackには同じ論文を一度だけ送る
+    assert_eq!(slack_notification.find_not_send(slack_url).unwrap().len(), 3);
+}
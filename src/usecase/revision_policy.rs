@@ -0,0 +1,101 @@
+use crate::domain::arxiv_paper::{Paper, PaperVersion};
+use crate::domain::text_diff::is_changed;
+
+// 通知済みの論文の改訂版を再度通知するかどうか
+#[derive(Debug, Clone, PartialEq)]
+pub enum RevisionPolicy {
+    // 初めての論文だけ通知する
+    NewOnly,
+    // 新しいバージョンが出るたびに通知する
+    AllVersions,
+    // 採択が新たにコメントに書かれた場合か、タイトルやアブストラクトが変わった場合だけ通知する
+    RevisionsWithChanges,
+}
+
+impl RevisionPolicy {
+    pub fn parse(policy: &str) -> anyhow::Result<Self> {
+        match policy {
+            "new_only" => Ok(RevisionPolicy::NewOnly),
+            "all_versions" => Ok(RevisionPolicy::AllVersions),
+            "revisions_with_changes" => Ok(RevisionPolicy::RevisionsWithChanges),
+            _ => anyhow::bail!(
+                "Unknown revision policy `{}` (use one of new_only, all_versions, revisions_with_changes)",
+                policy
+            ),
+        }
+    }
+
+    // notified: 同じslackにこの論文を通知したことがあるかどうか
+    pub fn should_notify(&self, paper: &Paper, notified: bool) -> bool {
+        if !notified {
+            return true;
+        }
+        match self {
+            RevisionPolicy::NewOnly => false,
+            RevisionPolicy::AllVersions => true,
+            // 前のバージョンの記録がなければ比較できないので通知しない
+            RevisionPolicy::RevisionsWithChanges => match paper.previous_version() {
+                Some(previous) => has_changes(previous, paper),
+                None => false,
+            },
+        }
+    }
+}
+
+fn has_changes(previous: &PaperVersion, paper: &Paper) -> bool {
+    (!previous.is_accepted && paper.is_accepted)
+        || is_changed(&previous.title, &paper.title)
+        || is_changed(&previous.summary, &paper.summary)
+}
+
+#[test]
+fn test_revision_policy() {
+    use crate::domain::arxiv_id::ArxivId;
+    use crate::domain::arxiv_paper::{Category, PaperId};
+    let date = chrono::NaiveDateTime::from_timestamp(0, 0);
+    let version = |version: u32, comment: &str, is_accepted: bool| PaperVersion {
+        version,
+        title: "Face Parsing".to_string(),
+        summary: "We parse faces.".to_string(),
+        comment: comment.to_string(),
+        is_accepted,
+        updated: date,
+    };
+    let mut paper = Paper {
+        id: PaperId(1),
+        arxiv_id: Some(ArxivId::parse("2104.01234v2").unwrap()),
+        title: "Face Parsing".to_string(),
+        url: "http://arxiv.org/abs/2104.01234v2".to_string(),
+        pdf_url: "http://arxiv.org/pdf/2104.01234v2".to_string(),
+        authors: vec![],
+        category: Category { id: 1, name: "cs.CV".to_string() },
+        categories: vec![],
+        summary: "We  parse faces.".to_string(),
+        comment: "10 pages".to_string(),
+        is_accepted: false,
+        journal_ref: None,
+        doi: None,
+        classes: vec![],
+        venue: None,
+        links: vec![],
+        counts: Default::default(),
+        versions: vec![version(1, "9 pages", false), version(2, "10 pages", false)],
+        updated: date,
+        published: date,
+    };
+    assert!(RevisionPolicy::parse("all").is_err());
+    let policy = RevisionPolicy::parse("revisions_with_changes").unwrap();
+    assert!(policy.should_notify(&paper, false));
+    // ページ数や空白が変わっただけでは通知しない
+    assert!(!policy.should_notify(&paper, true));
+    assert!(RevisionPolicy::AllVersions.should_notify(&paper, true));
+    assert!(!RevisionPolicy::NewOnly.should_notify(&paper, true));
+
+    paper.comment = "Accepted to CVPR 2021".to_string();
+    paper.is_accepted = true;
+    assert!(policy.should_notify(&paper, true));
+    paper.versions[0].is_accepted = true;
+    assert!(!policy.should_notify(&paper, true));
+    paper.title = "Face Parsing in the Wild".to_string();
+    assert!(policy.should_notify(&paper, true));
+}
@@ -3,7 +3,7 @@ use crate::domain::slack_api::SlackAPITrait;
 
 pub trait SlackAPIUseCaseTrait {
     fn send(&self, message: &str);
-    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>) -> Vec<(Paper, String)>;
+    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> Vec<(Paper, String)>;
 }
 
 
@@ -30,7 +30,7 @@ impl<A> SlackAPIUseCaseTrait for SlackAPIUseCase<A>
     fn send(&self, message: &str) {
         self.slack_api.send(message);
     }
-    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>) -> Vec<(Paper, String)> {
-        self.slack_api.build_messages(papers, keywords)
+    fn build_messages(&self, papers: &Vec<Paper>, keywords: &Option<Vec<String>>, follow_authors: &Option<Vec<String>>) -> Vec<(Paper, String)> {
+        self.slack_api.build_messages(papers, keywords, follow_authors)
     }
 }
@@ -5,6 +5,7 @@ use crate::domain::slack_notification::{NewSlackNotification, SlackNotificationR
 pub trait SlackNotificationUseCaseTrait {
     fn enqueue_slack_notifications(&self, notifications: &Vec<NewSlackNotification>) -> anyhow::Result<usize>;
     fn find_not_send(&self, slack_url: &str) -> anyhow::Result<Vec<Paper>>;
+    fn find_notified(&self, slack_url: &str, paper_ids: &[PaperId]) -> anyhow::Result<Vec<PaperId>>;
     fn mark_as_send(&self, slack_url: &str, id: &PaperId) -> anyhow::Result<usize>;
     fn delete(&self, slack_url: &str, paper_id: &PaperId) -> anyhow::Result<()>;
 }
@@ -36,6 +37,9 @@ impl<S> SlackNotificationUseCaseTrait for SlackNotificationUseCase<S>
         self.slack_notification_repository.find_not_send(slack_url)
 
     }
+    fn find_notified(&self, slack_url: &str, paper_ids: &[PaperId]) -> anyhow::Result<Vec<PaperId>> {
+        self.slack_notification_repository.find_notified(slack_url, paper_ids)
+    }
     fn mark_as_send(&self, slack_url: &str, id: &PaperId) -> anyhow::Result<usize> {
         self.slack_notification_repository.mark_as_send(slack_url, id)
     }
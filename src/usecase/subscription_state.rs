@@ -0,0 +1,52 @@
+use chrono::NaiveDateTime;
+use crate::domain::arxiv_paper::PaperId;
+use crate::domain::subscription_state::{SubscriptionState, SubscriptionStateRepositoryTrait};
+
+pub trait SubscriptionStateUseCaseTrait {
+    fn find_last_updated(&self, subscription: &str) -> anyhow::Result<Option<NaiveDateTime>>;
+    fn update_last_updated(&self, subscription: &str, updated: NaiveDateTime) -> anyhow::Result<usize>;
+    fn add_papers(&self, subscription: &str, paper_ids: &[PaperId]) -> anyhow::Result<usize>;
+    fn find_paper_ids(&self, subscription: &str) -> anyhow::Result<Vec<PaperId>>;
+}
+
+#[derive(Clone)]
+pub struct SubscriptionStateUseCase<S>
+    where S: SubscriptionStateRepositoryTrait
+{
+    pub subscription_state_repository: S,
+}
+
+impl<S> SubscriptionStateUseCase<S>
+    where S: SubscriptionStateRepositoryTrait
+{
+    pub fn new(subscription_state_repository: S) -> Self {
+        Self {
+            subscription_state_repository
+        }
+    }
+}
+
+impl<S> SubscriptionStateUseCaseTrait for SubscriptionStateUseCase<S>
+    where S: SubscriptionStateRepositoryTrait
+{
+    fn find_last_updated(&self, subscription: &str) -> anyhow::Result<Option<NaiveDateTime>> {
+        let state = self.subscription_state_repository.find(subscription)?;
+        Ok(state.map(|s| s.last_updated))
+    }
+    fn update_last_updated(&self, subscription: &str, updated: NaiveDateTime) -> anyhow::Result<usize> {
+        // 古い日時で上書きしないようにする
+        match self.find_last_updated(subscription)? {
+            Some(last_updated) if last_updated >= updated => Ok(0),
+            _ => self.subscription_state_repository.save(&SubscriptionState {
+                subscription: subscription.to_string(),
+                last_updated: updated,
+            }),
+        }
+    }
+    fn add_papers(&self, subscription: &str, paper_ids: &[PaperId]) -> anyhow::Result<usize> {
+        self.subscription_state_repository.add_papers(subscription, paper_ids)
+    }
+    fn find_paper_ids(&self, subscription: &str) -> anyhow::Result<Vec<PaperId>> {
+        self.subscription_state_repository.find_paper_ids(subscription)
+    }
+}